@@ -1,4 +1,212 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Releases at which the bindings change, for each of these that's at or below the version we build against a
+/// `ws_ge_<major>_<minor>` cfg flag is set.
+const VERSION_CFGS: [(u32, u32); 5] = [(3, 0), (3, 4), (3, 6), (4, 0), (4, 2)];
+
+/// The release selected with one of the ws-* features, if any.
+fn feature_version() -> Option<&'static str> {
+    if env::var_os("CARGO_FEATURE_WS_4_2").is_some() {
+        Some("4.2.0")
+    } else if env::var_os("CARGO_FEATURE_WS_4_0").is_some() {
+        Some("4.0.0")
+    } else if env::var_os("CARGO_FEATURE_WS_3_6").is_some() {
+        Some("3.6.0")
+    } else if env::var_os("CARGO_FEATURE_WS_2_6").is_some() {
+        Some("2.6.0")
+    } else {
+        None
+    }
+}
+
+/// Parse a "major.minor.micro" version string.
+fn parse_version(version: &str) -> (u32, u32, u32) {
+    let mut parts = version.trim().split('.').map(|p| p.parse::<u32>().unwrap_or(0));
+    let major = parts.next().unwrap_or(0);
+    let minor = parts.next().unwrap_or(0);
+    let micro = parts.next().unwrap_or(0);
+    (major, minor, micro)
+}
+
+/// Read the version from the headers in an installation, ws_version.h exists from 3.0, before that config.h holds it.
+fn header_version(include_dir: &Path) -> Option<String> {
+    let define = |contents: &str, name: &str| -> Option<String> {
+        contents
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("#define "))
+            .filter_map(|l| l.trim().strip_prefix(name))
+            .filter(|l| l.starts_with(char::is_whitespace))
+            .map(|l| l.trim().trim_matches('"').to_string())
+            .next()
+    };
+    if let Ok(contents) = fs::read_to_string(include_dir.join("ws_version.h")) {
+        let major = define(&contents, "VERSION_MAJOR")?;
+        let minor = define(&contents, "VERSION_MINOR")?;
+        let micro = define(&contents, "VERSION_MICRO")?;
+        return Some(format!("{}.{}.{}", major, minor, micro));
+    }
+    let contents = fs::read_to_string(include_dir.join("config.h")).ok()?;
+    define(&contents, "VERSION")
+}
+
+/// Find wireshark and glib and tell cargo how to link against them if `link` is set, returns the version found, if
+/// any, and the include paths.
+fn find_wireshark(link: bool) -> (Option<String>, Vec<PathBuf>) {
+    let glib = pkg_config::Config::new().cargo_metadata(link).probe("glib-2.0");
+    let mut include_paths = match &glib {
+        Ok(library) => library.include_paths.clone(),
+        Err(_) => {
+            if link {
+                println!("cargo:rustc-link-lib=glib-2.0");
+            }
+            vec![]
+        }
+    };
+
+    // An explicit installation directory takes precedence over whatever pkg-config knows about.
+    if let Some(dir) = env::var_os("WIRESHARK_DIR").map(PathBuf::from) {
+        if link {
+            for lib in &["lib", "lib64"] {
+                println!("cargo:rustc-link-search=native={}", dir.join(lib).display());
+            }
+            println!("cargo:rustc-link-lib=wireshark");
+        }
+        let include_dir = dir.join("include").join("wireshark");
+        let version = header_version(&include_dir);
+        include_paths.push(include_dir);
+        return (version, include_paths);
+    }
+
+    // Wireshark's pkg-config file pulls in glib as well.
+    match pkg_config::Config::new().cargo_metadata(link).probe("wireshark") {
+        Ok(library) => {
+            include_paths.extend(library.include_paths);
+            (Some(library.version), include_paths)
+        }
+        Err(_) => {
+            // Not known to pkg-config, hope it's in the default paths.
+            if link {
+                println!("cargo:rustc-link-lib=wireshark");
+            }
+            (None, include_paths)
+        }
+    }
+}
+
+/// Compile the C side of the layout tests against the headers, `enabled_cfgs` are the version cfg flags that are set.
+#[cfg(feature = "layout-tests")]
+fn build_layout_shim(include_paths: &[PathBuf], enabled_cfgs: &[String]) {
+    println!("cargo:rerun-if-changed=tests/layout.c");
+    let mut build = cc::Build::new();
+    build.file("tests/layout.c").include("src/epan");
+    for path in include_paths {
+        build.include(path);
+    }
+    for cfg in enabled_cfgs {
+        build.define(&cfg.to_uppercase(), None);
+    }
+    build.compile("wireshark_layout");
+}
+
+/// Strips the C prefixes from the generated enum variants, so they match the hand written bindings.
+#[cfg(feature = "bindgen")]
+#[derive(Debug)]
+struct StripEnumPrefix;
+
+#[cfg(feature = "bindgen")]
+impl bindgen::callbacks::ParseCallbacks for StripEnumPrefix {
+    fn enum_variant_name(
+        &self,
+        _enum_name: Option<&str>,
+        original_variant_name: &str,
+        _variant_value: bindgen::callbacks::EnumVariantValue,
+    ) -> Option<String> {
+        for prefix in &["FT_", "HF_REF_TYPE_"] {
+            if let Some(stripped) = original_variant_name.strip_prefix(prefix) {
+                return Some(stripped.to_string());
+            }
+        }
+        None
+    }
+}
+
+/// Generate the epan structs from the installed headers instead of using the hand written ones.
+#[cfg(feature = "bindgen")]
+fn generate_bindings(include_paths: &[PathBuf], out_dir: &Path) {
+    println!("cargo:rerun-if-changed=src/epan/bindings.h");
+    let mut builder = bindgen::Builder::default()
+        .header("src/epan/bindings.h")
+        .allowlist_type("field_info")
+        .allowlist_type("_?header_field_info")
+        .allowlist_type("hf_register_info")
+        .allowlist_type("_?fvalue_t")
+        .allowlist_type("proto_plugin")
+        .allowlist_type("ftenum")
+        .allowlist_type("hf_ref_type")
+        .allowlist_type("_?GPtrArray")
+        .rustified_enum("ftenum|hf_ref_type")
+        // The tvb is opaque, use our own so the types line up.
+        .blocklist_type("_?tvbuff(_t)?")
+        .raw_line("use super::tvbuff::tvbuff_t;")
+        .derive_default(false)
+        .derive_debug(true)
+        .layout_tests(false)
+        .parse_callbacks(Box::new(StripEnumPrefix));
+    for path in include_paths {
+        builder = builder.clang_arg(format!("-I{}", path.display()));
+    }
+    builder
+        .generate()
+        .expect("Unable to generate the wireshark bindings")
+        .write_to_file(out_dir.join("bindings.rs"))
+        .expect("Unable to write the wireshark bindings");
+}
+
 fn main() {
-    println!("cargo:rustc-link-lib=wireshark");
-    println!("cargo:rustc-link-lib=glib-2.0");
+    println!("cargo:rerun-if-env-changed=WIRESHARK_VERSION");
+    println!("cargo:rerun-if-env-changed=WIRESHARK_DIR");
+
+    // With the dynamic feature the functions are looked up at runtime, so nothing is linked.
+    let link = env::var_os("CARGO_FEATURE_DYNAMIC").is_none();
+    let (found, include_paths) = find_wireshark(link);
+
+    // Explicit choices beat what we found, without anything we fall back to 2.6.
+    let version = env::var("WIRESHARK_VERSION")
+        .ok()
+        .or_else(|| feature_version().map(String::from))
+        .or(found)
+        .unwrap_or_else(|| String::from("2.6.0"));
+    let (major, minor, micro) = parse_version(&version);
+
+    // Emit the cfg flags that select the bindings.
+    let mut enabled_cfgs = vec![];
+    for (cfg_major, cfg_minor) in VERSION_CFGS.iter() {
+        let name = format!("ws_ge_{}_{}", cfg_major, cfg_minor);
+        println!("cargo:rustc-check-cfg=cfg({})", name);
+        if (major, minor) >= (*cfg_major, *cfg_minor) {
+            println!("cargo:rustc-cfg={}", name);
+            enabled_cfgs.push(name);
+        }
+    }
+
+    // Write the version constants, these end up in the version module.
+    let out_dir = env::var("OUT_DIR").unwrap();
+    fs::write(
+        Path::new(&out_dir).join("version.rs"),
+        format!(
+            "pub const MAJOR: u32 = {};\npub const MINOR: u32 = {};\npub const MICRO: u32 = {};\npub const VERSION: &str = \"{}.{}.{}\";\npub const RELEASE: &str = \"{}.{}\\0\";\n",
+            major, minor, micro, major, minor, micro, major, minor
+        ),
+    )
+    .unwrap();
+
+    #[cfg(feature = "bindgen")]
+    generate_bindings(&include_paths, Path::new(&out_dir));
+    #[cfg(feature = "layout-tests")]
+    build_layout_shim(&include_paths, &enabled_cfgs);
+    #[cfg(not(any(feature = "bindgen", feature = "layout-tests")))]
+    let _ = (include_paths, enabled_cfgs);
 }
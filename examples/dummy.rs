@@ -106,6 +106,9 @@ impl MyDissector {
 }
 
 impl dissector::Dissector for MyDissector {
+    /// We keep a count of the packets seen in each conversation.
+    type ConversationState = usize;
+
     /// This function is called during setup, it must provide all PacketFields we may end up using for registration.
     fn get_fields(self: &Self) -> Vec<dissector::PacketField> {
         let mut f = Vec::new();
@@ -138,7 +141,29 @@ impl dissector::Dissector for MyDissector {
     }
 
     /// The main dissection function, this is called whenever we are to dissect something.
-    fn dissect(self: &Self, proto: &mut epan::ProtoTree, tvb: &mut epan::TVB) -> usize {
+    fn dissect(
+        self: &Self,
+        pinfo: &mut epan::PacketInfo,
+        conversation: &mut epan::Conversation<usize>,
+        proto: &mut epan::ProtoTree,
+        tvb: &mut epan::TVB,
+    ) -> usize {
+        // Show our protocol in the packet list, with the frame number in the info column.
+        pinfo.col_set_str(epan::Column::PROTOCOL, "testproto\0");
+        pinfo.col_add_fstr(epan::Column::INFO, format_args!("Test packet {}", pinfo.frame_number()));
+
+        // The conversation state lives across packets, packets are dissected again when they're shown, so only count
+        // them the first time. The count at that point is stored against the frame, to show it on the later passes.
+        if !pinfo.visited() {
+            if let Ok(mut count) = conversation.state() {
+                *count += 1;
+                pinfo.add_proto_data(0, *count);
+            }
+        }
+        if let Some(count) = pinfo.get_proto_data::<usize>(0).copied() {
+            pinfo.col_append_fstr(epan::Column::INFO, format_args!(", #{} in conversation", count));
+        }
+
         // Usually, we want to use an offset and increment it as we progress through the packet.
         let mut offset = 0;
 
@@ -219,23 +244,6 @@ impl dissector::Dissector for MyDissector {
     }
 }
 
-use std::rc::Rc;
-
-/// This function is the main entry point for the plugin. It's the only symbol called automatically.
-#[no_mangle]
-pub fn plugin_register() {
-    let z = Rc::new(MyDissector::new());
-    dissector::setup(z);
-}
-
-// And we need these public symbols to tell wireshark we are a plugin that's made for the right version.
-#[no_mangle]
-static plugin_version: [libc::c_char; 4] = [50, 46, 54, 0]; // "2.6"
-#[no_mangle]
-static plugin_release: [libc::c_char; 4] = [50, 46, 54, 0]; // "2.6"
-
-// Later versions of wireshark also want these integers.
-#[no_mangle]
-static plugin_want_major: u32 = 2;
-#[no_mangle]
-static plugin_want_minor: u32 = 6;
+// This creates the plugin_register function that's called by wireshark, as well as the public symbols that tell
+// wireshark we are a plugin that's made for the right version.
+wireshark_dissector_rs::wireshark_plugin!(MyDissector::new());
@@ -22,7 +22,15 @@ use core::fmt::Debug;
 ///
 /// Whenever the dissector is invoked, it's [`Dissector::dissect()`] (or [`Dissector::heuristic_dissect()`]) method will be called with the
 /// protocol tree and data buffer.
-pub trait Dissector {
+///
+/// The registration methods that take `&mut self` are only called while the plugin still owns the dissector
+/// exclusively. After registration the dissector is only used through a shared reference, wireshark may call it from
+/// multiple threads, hence the `Send + Sync` requirement.
+pub trait Dissector: Send + Sync {
+    /// The state this dissector keeps for every conversation, use `()` if there is none. It is created with
+    /// [`Default`] the first time [`epan::Conversation::state`] is called for a conversation.
+    type ConversationState: Default + 'static;
+
     /// This function must return a vector of all the possible fields the dissector will end up using.
     fn get_fields(self: &Self) -> Vec<PacketField>;
 
@@ -31,19 +39,42 @@ pub trait Dissector {
     fn set_field_indices(self: &mut Self, hf_indices: Vec<(PacketField, epan::proto::HFIndex)>);
 
     /// Called when there is something to dissect, so probably called for every packet. This function must return how
-    /// many bytes it used from the tvb.
-    fn dissect(self: &Self, _proto: &mut epan::ProtoTree, _tvb: &mut epan::TVB) -> usize {
+    /// many bytes it used from the tvb. The [`epan::PacketInfo`] provides the frame number, addresses and such, the
+    /// [`epan::Conversation`] the state of the conversation this packet belongs to.
+    fn dissect(
+        self: &Self,
+        _pinfo: &mut epan::PacketInfo,
+        _conversation: &mut epan::Conversation<Self::ConversationState>,
+        _proto: &mut epan::ProtoTree,
+        _tvb: &mut epan::TVB,
+    ) -> usize {
         0
     }
 
     /// Full name, short_name, filter_name
     fn get_protocol_name(self: &Self) -> (&'static str, &'static str, &'static str);
 
+    /// This method can return names to register the dissector with, such that others can find it by name, for example
+    /// with `find_dissector` or `Dissector.get` in Lua. The handle of the first name is the one used for the
+    /// registrations.
+    fn get_dissector_names(self: &Self) -> Vec<&'static str> {
+        return vec![];
+    }
+
     /// This method should return a list that describes how this dissector's should be registered.
     fn get_registration(self: &Self) -> Vec<Registration> {
         return vec![Registration::Post];
     }
 
+    /// This function must return all the expert infos the dissector will end up using.
+    fn get_expert_fields(self: &Self) -> Vec<ExpertField> {
+        return vec![];
+    }
+
+    /// After the expert infos are registered, this function is called to provide the [`epan::expert::ExpertIndex`]
+    /// that should be used to refer to them.
+    fn set_expert_indices(self: &mut Self, _ei_indices: Vec<(ExpertField, epan::expert::ExpertIndex)>) {}
+
     /// This function should return the number of tree foldouts to register.
     fn get_tree_count(self: &Self) -> usize {
         return 0;
@@ -53,14 +84,63 @@ pub trait Dissector {
     /// subtree elements to protocol items.
     fn set_tree_indices(self: &mut Self, _ett_indices: Vec<epan::proto::ETTIndex>) {}
 
+    /// This function should return the dissector tables this protocol provides, other dissectors can register in these
+    /// to be called for a key, like a message type, see [`epan::DissectorTable`].
+    fn get_dissector_tables(self: &Self) -> Vec<DissectorTableDefinition> {
+        return vec![];
+    }
+
+    /// After the dissector tables are registered, this function is called with the tables that can be used to
+    /// dispatch the payload from [`Dissector::dissect()`].
+    fn set_dissector_tables(self: &mut Self, _tables: Vec<(DissectorTableDefinition, epan::DissectorTable)>) {}
+
+    /// This function should return the names of the heuristic dissector lists this protocol provides, like `myproto`.
+    /// Other dissectors can add themselves to these with [`Registration::Heuristic`], see [`epan::HeuristicList`].
+    fn get_heuristic_lists(self: &Self) -> Vec<&'static str> {
+        return vec![];
+    }
+
+    /// After the heuristic lists are registered, this function is called with the lists that can be tried on the
+    /// payload from [`Dissector::dissect()`].
+    fn set_heuristic_lists(self: &mut Self, _lists: Vec<(&'static str, epan::HeuristicList)>) {}
+
+    /// This function should return the preferences this protocol has, these are shown in wireshark's protocol
+    /// preferences.
+    fn get_preferences(self: &Self) -> Vec<PreferenceDefinition> {
+        return vec![];
+    }
+
+    /// After the preferences are registered, this function is called with the [`epan::Preference`] handles that can
+    /// be used to read the current values.
+    fn set_preferences(self: &mut Self, _preferences: Vec<(PreferenceDefinition, epan::Preference)>) {}
+
+    /// Called when the user applied changes to the preferences, the new values can be read from the preferences.
+    fn preferences_changed(self: &Self) {}
+
+    /// This method can return the tables to reassemble fragmented data in. The fields and subtrees that show the
+    /// fragments are registered along with them.
+    fn get_reassembly_tables(self: &Self) -> Vec<ReassemblyTableDefinition> {
+        return vec![];
+    }
+
+    /// After the reassembly tables are registered, this function is called with the [`epan::ReassemblyTable`] handles
+    /// that are used to add fragments.
+    fn set_reassembly_tables(self: &mut Self, _tables: Vec<(ReassemblyTableDefinition, epan::ReassemblyTable)>) {}
+
     /// This function is called when using a heuristic dissection.
-    fn heuristic_dissect(self: &Self, _proto: &mut epan::ProtoTree, _tvb: &mut epan::TVB) -> bool {
+    fn heuristic_dissect(
+        self: &Self,
+        _pinfo: &mut epan::PacketInfo,
+        _conversation: &mut epan::Conversation<Self::ConversationState>,
+        _proto: &mut epan::ProtoTree,
+        _tvb: &mut epan::TVB,
+    ) -> bool {
         false
     }
 }
 
 //-------------------------------------------------
-pub type FieldType = epan::ftypes::ftenum;
+pub type FieldType = epan::FieldType;
 pub type FieldDisplay = epan::proto::FieldDisplay;
 
 /// A type to allow both dynamic string creation as well as static strings, such that PacketField
@@ -115,6 +195,38 @@ impl PacketField {
     }
 }
 
+pub type ExpertGroup = epan::expert::ExpertGroup;
+pub type ExpertSeverity = epan::expert::ExpertSeverity;
+
+/// Specification for an expert info, used to flag problems in the packets.
+#[derive(Debug, Clone)]
+pub struct ExpertField {
+    /// This is the internal name of the expert info (`proto.bad_length`).
+    pub abbrev: StringContainer,
+    /// This is the text shown for it, unless text is given when adding it.
+    pub summary: StringContainer,
+    /// What kind of problem this is about.
+    pub group: ExpertGroup,
+    /// How bad the problem is.
+    pub severity: ExpertSeverity,
+}
+
+impl ExpertField {
+    pub const fn fixed(
+        abbrev: &'static str,
+        summary: &'static str,
+        group: ExpertGroup,
+        severity: ExpertSeverity,
+    ) -> Self {
+        ExpertField {
+            abbrev: StringContainer::StaticStr(abbrev),
+            summary: StringContainer::StaticStr(summary),
+            group,
+            severity,
+        }
+    }
+}
+
 // https://rust-lang.github.io/rfcs/0418-struct-variants.html
 // This is so fancy
 /// Specifies how to register this dissector.
@@ -136,6 +248,23 @@ pub enum Registration {
         /// The value min-max ranges to register for.
         ranges: Vec<(u32, u32)>,
     },
+    /// Register based on a field abbreviation and a range of integers the user can change in the protocol preferences,
+    /// this calls `dissector_add_uint_range_with_preference`. When the preference changes wireshark removes the
+    /// dissector from the old values in the table and adds it for the new ones.
+    UIntRangePreference {
+        /// The table to register for, like `tcp.port` or `udp.port`.
+        abbrev: &'static str,
+        /// The ranges until the user changes them, like "1234,5000-5010".
+        default: &'static str,
+    },
+    /// Register a field abbreviation and a string value, this calls `dissector_add_string`, this for example allows
+    /// registering for a media type or an application protocol negotiated by TLS.
+    String {
+        /// The table to register for, like `media_type` or `tls.alpn`.
+        abbrev: &'static str,
+        /// The value in this table to register.
+        pattern: &'static str,
+    },
     /// Register this dissector for manual 'decode as' functionality.
     DecodeAs {
         /// The table to register for.
@@ -154,11 +283,119 @@ pub enum Registration {
     },
 }
 
-use std::rc::Rc;
+/// The kind of key a dissector table provided by this dissector uses.
+#[derive(Debug, Clone, Copy)]
+pub enum DissectorTableKey {
+    /// Integer keys, the field type must be one of the unsigned integers, the display is used to show the keys.
+    UInt {
+        /// The integer type of the keys, like UINT8 or UINT32.
+        field_type: FieldType,
+        /// How the keys are shown.
+        display: FieldDisplay,
+    },
+    /// String keys, these are case sensitive.
+    String,
+}
+
+/// Describes a dissector table to register, other dissectors can register in it with [`Registration::UInt`] or
+/// [`Registration::String`] using the table's name as abbreviation.
+#[derive(Debug, Clone)]
+pub struct DissectorTableDefinition {
+    /// The name used to register in the table, like `myproto.msg_type`.
+    pub name: &'static str,
+    /// The name shown in the user interface.
+    pub ui_name: &'static str,
+    /// The kind of key this table uses.
+    pub key: DissectorTableKey,
+}
+
+/// The type of a preference and its default value.
+#[derive(Debug, Clone)]
+pub enum PreferenceKind {
+    /// A checkbox.
+    Bool {
+        /// Value until the user changes it.
+        default: bool,
+    },
+    /// An unsigned integer.
+    UInt {
+        /// Value until the user changes it.
+        default: u32,
+        /// The base the value is shown and entered in, like 10 or 16.
+        base: u32,
+    },
+    /// A choice between several values.
+    Enum {
+        /// Value until the user changes it.
+        default: i32,
+        /// The (name, description, value) of each choice, the description is what's shown.
+        values: Vec<(&'static str, &'static str, i32)>,
+        /// Show the choices as radio buttons instead of a dropdown.
+        radio_buttons: bool,
+    },
+    /// A text field.
+    String {
+        /// Value until the user changes it.
+        default: &'static str,
+    },
+    /// A file path.
+    Filename {
+        /// Value until the user changes it.
+        default: &'static str,
+        /// Whether the file dialog asks for a file to save to, instead of one to open.
+        for_writing: bool,
+    },
+    /// A range of integers, like "1-5,7".
+    Range {
+        /// Value until the user changes it.
+        default: &'static str,
+        /// Largest value allowed in the range.
+        max_value: u32,
+    },
+}
+
+/// Describes a preference to register.
+#[derive(Debug, Clone)]
+pub struct PreferenceDefinition {
+    /// The name of the preference, it's stored as `<protocol filter name>.<name>`.
+    pub name: &'static str,
+    /// The title shown in the user interface.
+    pub title: &'static str,
+    /// Longer description, shown as tooltip.
+    pub description: &'static str,
+    /// The type and default value.
+    pub kind: PreferenceKind,
+}
+
+/// What fragments are matched by, besides their id.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ReassemblyKey {
+    /// The source and destination addresses.
+    Addresses,
+    /// The source and destination addresses and ports.
+    AddressesPorts,
+}
+
+/// Describes a table to reassemble fragmented data in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReassemblyTableDefinition {
+    /// Prefix of the filter names of the fragment fields, `myproto.msg` gives `myproto.msg.fragment` and so on.
+    pub abbrev: &'static str,
+    /// What is reassembled, like `Message`, this is used in the names of the fields and the reassembled data.
+    pub name: &'static str,
+    /// What the fragments are matched by.
+    pub key: ReassemblyKey,
+}
+
+/// The maximum number of dissectors that can be registered with [`setup()`].
+pub const MAX_DISSECTORS: usize = plugin::MAX_DISSECTORS;
+
 /// Pass the dissector for setup, this is the main entry function that registers the plugin.
 ///
-/// Currently, the one dissector that's handed in is stored in a global static. During setup we use it as a mutable
-/// after setup it will be immutable and multiple threads from wireshark may interact with it.
-pub fn setup<T: 'static + Dissector>(d: Rc<T>) {
+/// This can be called multiple times to register several protocols from a single plugin, up to
+/// [`MAX_DISSECTORS`]. Each dissector gets its own protocol id, fields, tree foldouts and dissector handle.
+/// Setup takes ownership of the dissector, it is mutable while wireshark registers the protocol and immutable after
+/// that, at which point multiple threads from wireshark may interact with it.
+pub fn setup<T: 'static + Dissector>(d: T) {
     plugin::setup(d);
 }
@@ -27,16 +27,101 @@
 #![allow(dead_code)]
 extern crate libc;
 
+use crate::util;
+
+// Raw bindings generated from the installed headers, the modules below use the structs from this if it's enabled.
+#[cfg(feature = "bindgen")]
+pub mod bindings;
+
+// Looking up the functions at runtime, this holds the macro the modules below declare their functions with.
+#[macro_use]
+pub mod dynamic;
+
 // These files follow the same structure as the header files.
+pub mod address;
+pub mod column;
+pub mod column_utils;
+pub mod conversation;
+#[allow(clippy::module_inception)]
+pub mod epan;
+pub mod expert;
+pub mod frame_data;
 pub mod ftypes;
 pub mod glib;
+pub mod nstime;
 pub mod packet;
 pub mod packet_info;
+pub mod packet_tcp;
+pub mod prefs;
 pub mod proto;
+pub mod proto_data;
 pub mod range;
+pub mod reassemble;
 pub mod tvbuff;
+pub mod wmem;
+pub mod wmem_user_cb;
+
+// The bindings can only match one wireshark release at a time.
+#[cfg(any(
+    all(feature = "ws-2-6", any(feature = "ws-3-6", feature = "ws-4-0", feature = "ws-4-2")),
+    all(feature = "ws-3-6", any(feature = "ws-4-0", feature = "ws-4-2")),
+    all(feature = "ws-4-0", feature = "ws-4-2")
+))]
+compile_error!("Only one of the ws-2-6, ws-3-6, ws-4-0 and ws-4-2 features can be enabled.");
 
-pub type FieldType = ftypes::ftenum;
+/// The type of a field, this is the same for all wireshark releases, it converts to and from [`ftypes::ftenum`],
+/// which holds the discriminants of the release the bindings are made for.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum FieldType {
+    #[default]
+    NONE, /* used for text labels with no value */
+    PROTOCOL,
+    BOOLEAN, /* TRUE and FALSE come from <glib.h> */
+    CHAR,    /* 1-octet character as 0-255 */
+    UINT8,
+    UINT16,
+    UINT24, /* really a UINT32, but displayed as 6 hex-digits if FD_HEX*/
+    UINT32,
+    UINT40, /* really a UINT64, but displayed as 10 hex-digits if FD_HEX*/
+    UINT48, /* really a UINT64, but displayed as 12 hex-digits if FD_HEX*/
+    UINT56, /* really a UINT64, but displayed as 14 hex-digits if FD_HEX*/
+    UINT64,
+    INT8,
+    INT16,
+    INT24, /* same as for UINT24 */
+    INT32,
+    INT40, /* same as for UINT40 */
+    INT48, /* same as for UINT48 */
+    INT56, /* same as for UINT56 */
+    INT64,
+    IEEE_11073_SFLOAT,
+    IEEE_11073_FLOAT,
+    FLOAT,
+    DOUBLE,
+    ABSOLUTE_TIME,
+    RELATIVE_TIME,
+    STRING,      /* counted string, with no null terminator */
+    STRINGZ,     /* null-terminated string */
+    UINT_STRING, /* counted string, with count being the first part of the value */
+    ETHER,
+    BYTES,
+    UINT_BYTES,
+    IPv4,
+    IPv6,
+    IPXNET,
+    FRAMENUM, /* a UINT32, but if selected lets you go to frame with that number */
+    GUID,     /* GUID, UUID */
+    OID,      /* OBJECT IDENTIFIER */
+    EUI64,
+    AX25,
+    VINES,
+    REL_OID, /* RELATIVE-OID */
+    SYSTEM_ID,
+    STRINGZPAD, /* null-padded string */
+    FCWWN,
+    STRINGZTRUNC, /* null-truncated string, registered as STRINGZ before 3.4 */
+}
 pub type FieldDisplay = proto::FieldDisplay;
 pub type Encoding = proto::Encoding;
 /*
@@ -78,11 +163,16 @@ impl FValue<'_> {
         return FValue { value: v };
     }
 
-    /// Obtain the enum that represents the type of data held by the value.
+    /// Obtain the raw enum that represents the type of data held by the value, its values depend on the release.
     pub fn ftenum(&self) -> ftypes::ftenum {
         unsafe { ftypes::fvalue_type_ftenum(self.value as *const ftypes::fvalue_t) }
     }
 
+    /// Obtain the type of data held by the value.
+    pub fn field_type(&self) -> FieldType {
+        self.ftenum().into()
+    }
+
     /// Retrieve an unsigned integer, should only be called if ftenum returns an integer-type.
     pub fn get_uinteger(&self) -> u32 {
         unsafe { ftypes::fvalue_get_uinteger(self.value as *const ftypes::fvalue_t) }
@@ -129,15 +219,15 @@ impl FValue<'_> {
 impl Debug for FValue<'_> {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "FValue<'_> {{ ")?;
-        write!(f, "type: \"{:?}\", ", self.ftenum())?;
-        match self.ftenum() {
-            ftypes::ftenum::UINT8 => write!(f, "value: {:?}", self.get_uinteger())?,
-            ftypes::ftenum::UINT16 => write!(f, "value: {:?}", self.get_uinteger())?,
-            ftypes::ftenum::UINT32 => write!(f, "value: {:?}", self.get_uinteger())?,
-            ftypes::ftenum::INT8 => write!(f, "value: {:?}", self.get_sinteger())?,
-            ftypes::ftenum::INT16 => write!(f, "value: {:?}", self.get_sinteger())?,
-            ftypes::ftenum::INT32 => write!(f, "value: {:?}", self.get_sinteger())?,
-            //~ ftypes::ftenum::BYTES => write!(f, "value: {:?}", self.get())?,
+        write!(f, "type: \"{:?}\", ", self.field_type())?;
+        match self.field_type() {
+            FieldType::UINT8 => write!(f, "value: {:?}", self.get_uinteger())?,
+            FieldType::UINT16 => write!(f, "value: {:?}", self.get_uinteger())?,
+            FieldType::UINT32 => write!(f, "value: {:?}", self.get_uinteger())?,
+            FieldType::INT8 => write!(f, "value: {:?}", self.get_sinteger())?,
+            FieldType::INT16 => write!(f, "value: {:?}", self.get_sinteger())?,
+            FieldType::INT32 => write!(f, "value: {:?}", self.get_sinteger())?,
+            //~ FieldType::BYTES => write!(f, "value: {:?}", self.get())?,
             _ => write!(f, "value: ...")?,
         }
         write!(f, "}}")
@@ -180,16 +270,16 @@ impl HeaderFieldInfo {
     }
 
     /// Obtain the field type enum.
-    pub fn type_(self: &Self) -> ftypes::ftenum {
+    pub fn type_(self: &Self) -> FieldType {
         unsafe {
-            return (*self.hfi).type_;
+            return (*self.hfi).type_.into();
         }
     }
 
     /// Obtain the field display enum.
     pub fn display(self: &Self) -> proto::FieldDisplay {
         unsafe {
-            return (*self.hfi).display;
+            return proto::FieldDisplay::from_raw((*self.hfi).display);
         }
     }
 }
@@ -200,7 +290,7 @@ impl Debug for HeaderFieldInfo {
         write!(f, "name: \"{}\", ", self.name())?;
         write!(f, "abbrev: \"{}\", ", self.abbrev())?;
         write!(f, "type_: {:?}, ", self.type_())?;
-        //~ write!(f, "display: {:?}", self.display())?;  // This segfaults, somewhere in 'gimli'.
+        write!(f, "display: {:?}, ", self.display())?;
         write!(f, "}}")
     }
 }
@@ -250,7 +340,7 @@ impl FieldInfo {
     }
 
     pub fn value(self: &Self) -> FValue {
-        unsafe { FValue::from(&(*self.fi).value) }
+        unsafe { FValue::from(&*(*self.fi).value_ptr()) }
     }
 }
 impl Debug for FieldInfo {
@@ -264,16 +354,78 @@ impl Debug for FieldInfo {
     }
 }
 
+// The field types each of the functions that add a value from Rust accepts.
+const UINT_TYPES: &[FieldType] = &[
+    FieldType::CHAR,
+    FieldType::UINT8,
+    FieldType::UINT16,
+    FieldType::UINT24,
+    FieldType::UINT32,
+    FieldType::FRAMENUM,
+];
+const INT_TYPES: &[FieldType] = &[FieldType::INT8, FieldType::INT16, FieldType::INT24, FieldType::INT32];
+const UINT64_TYPES: &[FieldType] = &[
+    FieldType::UINT40,
+    FieldType::UINT48,
+    FieldType::UINT56,
+    FieldType::UINT64,
+];
+const INT64_TYPES: &[FieldType] = &[FieldType::INT40, FieldType::INT48, FieldType::INT56, FieldType::INT64];
+const STRING_TYPES: &[FieldType] = &[
+    FieldType::STRING,
+    FieldType::STRINGZ,
+    FieldType::UINT_STRING,
+    FieldType::STRINGZPAD,
+    FieldType::STRINGZTRUNC,
+];
+const BYTES_TYPES: &[FieldType] = &[FieldType::BYTES, FieldType::UINT_BYTES];
+
+/// Wireshark treats adding a value of the wrong type as a bug in the dissector, check it before it gets there.
+fn check_field_type(hfindex: proto::HFIndex, types: &[FieldType]) -> Result<(), &'static str> {
+    // An index past the registered fields would throw in proto_registrar_get_nth, unwinding through the Rust frames.
+    if hfindex.0 < 0 || hfindex.0 >= unsafe { proto::proto_registrar_n() } {
+        return Err("The field is not registered.");
+    }
+    let hfinfo = unsafe { proto::proto_registrar_get_nth(hfindex.0 as u32) };
+    if hfinfo.is_null() {
+        return Err("The field is not registered.");
+    }
+    return check_type(unsafe { HeaderFieldInfo::from_ptr(hfinfo).type_() }, types);
+}
+
+/// Check whether the type of a field is one of the types.
+fn check_type(field_type: FieldType, types: &[FieldType]) -> Result<(), &'static str> {
+    if !types.contains(&field_type) {
+        return Err("The value doesn't match the type of the field.");
+    }
+    return Ok(());
+}
+
 /// Struct to represent a protocol tree, serves as a wrapper around the `proto_tree_*` C functions.
+///
+/// It also carries the packet info of the packet being dissected, calling other dissectors needs it.
 #[derive(Copy, Clone)]
 pub struct ProtoTree {
     tree: *mut proto::proto_tree,
+    pinfo: *mut packet_info::packet_info,
 }
 
 impl ProtoTree {
-    /// Function to make this structure from a raw pointer.
+    /// Function to make this structure from a raw pointer, without packet info it can't be used to call other
+    /// dissectors.
     pub unsafe fn from_ptr(tree: *mut proto::proto_tree) -> ProtoTree {
-        return ProtoTree { tree: tree };
+        return ProtoTree::from_ptrs(tree, std::ptr::null_mut());
+    }
+
+    /// Function to make this structure from the raw pointers the dissector gets called with.
+    ///
+    /// # Safety
+    ///
+    /// Both pointers must be the ones wireshark passed to the running dissector, or null, the tree is null when
+    /// wireshark only needs the side effects of the dissection. They belong to the packet being dissected, the result
+    /// must not be used after the dissector returns.
+    pub unsafe fn from_ptrs(tree: *mut proto::proto_tree, pinfo: *mut packet_info::packet_info) -> ProtoTree {
+        return ProtoTree { tree, pinfo };
     }
 
     /// Add an item to a proto_tree, using the text label registered to that item.
@@ -289,6 +441,7 @@ impl ProtoTree {
         unsafe {
             ProtoItem {
                 item: proto::proto_tree_add_item(self.tree, hfindex, tvb.into(), start as i32, length as i32, encoding),
+                pinfo: self.pinfo,
             }
         }
     }
@@ -317,153 +470,2343 @@ impl ProtoTree {
                         encoding,
                         &mut retval as *mut i32,
                     ),
+                    pinfo: self.pinfo,
                 },
                 retval,
             );
         }
     }
 
-    /// Function to retrieve all field info's currently associated with the protocol tree.
-    pub fn all_finfos(self: &mut Self) -> Vec<FieldInfo> {
-        let mut res: Vec<FieldInfo> = Vec::new();
-
-        // see wslua_field.c function wslua_all_field_infos
-        if self.tree.is_null()
-        // Not too sure when this happens... tree seems to be null when first invoked?
-        {
-            return res;
+    /// Add an unsigned integer item of up to 32 bits, returning the value, with the bitmask of the field applied.
+    pub fn add_item_ret_uint(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, u32) {
+        let mut retval: u32 = 0;
+        unsafe {
+            let item = proto::proto_tree_add_item_ret_uint(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                encoding,
+                &mut retval,
+            );
+            return (self.item(item), retval);
         }
+    }
+
+    /// Add an unsigned integer item of up to 64 bits, returning the value, with the bitmask of the field applied.
+    pub fn add_item_ret_uint64(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, u64) {
+        let mut retval: u64 = 0;
         unsafe {
-            let fields = proto::proto_all_finfos(self.tree);
-            for i in 0..(*fields).len() {
-                let field =
-                    std::mem::transmute::<*mut libc::c_void, *const proto::field_info>((*fields).index(i as isize));
-                res.push(FieldInfo::from_ptr(field));
+            let item = proto::proto_tree_add_item_ret_uint64(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                encoding,
+                &mut retval,
+            );
+            return (self.item(item), retval);
+        }
+    }
+
+    /// Add a signed integer item of up to 64 bits, returning the value. Before wireshark 3.0 the value is decoded here
+    /// and the bitmask of the field is not applied to it.
+    pub fn add_item_ret_int64(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, i64) {
+        #[cfg(ws_ge_3_0)]
+        {
+            let mut retval: i64 = 0;
+            unsafe {
+                let item = proto::proto_tree_add_item_ret_int64(
+                    self.tree,
+                    hfindex,
+                    tvb.into(),
+                    start as i32,
+                    length as i32,
+                    encoding,
+                    &mut retval,
+                );
+                return (self.item(item), retval);
             }
-            glib::g_ptr_array_free(fields, true);
-            // The field info's actually stay in scope, as they are part of the proto datastructure.
-            // the lua part also persists them after they're gone.
         }
-        return res;
+        #[cfg(not(ws_ge_3_0))]
+        return self.add_item_decoded(hfindex, tvb, start, length, encoding, |bytes: [u8; 8]| {
+            sign_extend(bytes, length)
+        });
     }
-}
 
-use std::ffi::CString;
+    /// Add a boolean item, returning the value, with the bitmask of the field applied.
+    pub fn add_item_ret_boolean(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, bool) {
+        let mut retval = proto::BoolValue::from(false);
+        unsafe {
+            let item = proto::proto_tree_add_item_ret_boolean(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                encoding,
+                &mut retval,
+            );
+            return (self.item(item), retval != proto::BoolValue::from(false));
+        }
+    }
 
-/// Struct to represent a protocol item, serves as a wrapper around the `proto_item_*` C functions.
-#[derive(Copy, Clone)]
-pub struct ProtoItem {
-    item: *mut proto::proto_item,
-}
-impl From<&mut ProtoItem> for *mut proto::proto_item {
-    fn from(field: &mut ProtoItem) -> Self {
-        return field.item;
+    /// Add a 4 byte float item, returning the value.
+    pub fn add_item_ret_float(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, f32) {
+        #[cfg(ws_ge_4_0)]
+        {
+            let mut retval: f32 = 0.0;
+            unsafe {
+                let item = proto::proto_tree_add_item_ret_float(
+                    self.tree,
+                    hfindex,
+                    tvb.into(),
+                    start as i32,
+                    4,
+                    encoding,
+                    &mut retval,
+                );
+                return (self.item(item), retval);
+            }
+        }
+        #[cfg(not(ws_ge_4_0))]
+        return self.add_item_decoded(hfindex, tvb, start, 4, encoding, f32::from_be_bytes);
     }
-}
 
-impl ProtoItem {
-    /// Replace text of item after it already has been created.
-    pub fn set_text(self: &mut Self, text: &str) {
-        let to_add = CString::new(text).unwrap().into_raw();
-        unsafe {
-            proto::proto_item_set_text(self.item.into(), to_add);
-            // and clean up the string again.
-            let _ = CString::from_raw(to_add);
+    /// Add an 8 byte double item, returning the value.
+    pub fn add_item_ret_double(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, f64) {
+        #[cfg(ws_ge_4_0)]
+        {
+            let mut retval: f64 = 0.0;
+            unsafe {
+                let item = proto::proto_tree_add_item_ret_double(
+                    self.tree,
+                    hfindex,
+                    tvb.into(),
+                    start as i32,
+                    8,
+                    encoding,
+                    &mut retval,
+                );
+                return (self.item(item), retval);
+            }
         }
+        #[cfg(not(ws_ge_4_0))]
+        return self.add_item_decoded(hfindex, tvb, start, 8, encoding, f64::from_be_bytes);
     }
 
-    /// Append to text of item after it has already been created.
-    pub fn append_text(self: &mut Self, text: &str) {
-        let to_add = CString::new(text).unwrap().into_raw();
+    /// Add a string item, returning the string. Wireshark converts it from the encoding to UTF-8, characters that
+    /// can't be converted are replaced.
+    pub fn add_item_ret_string(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, String) {
+        let mut retval: *const u8 = std::ptr::null();
+        let mut consumed: i32 = 0;
         unsafe {
-            proto::proto_item_append_text(self.item.into(), to_add);
-            let _ = CString::from_raw(to_add);
+            let item = proto::proto_tree_add_item_ret_string_and_length(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                encoding,
+                wmem::wmem_packet_scope(),
+                &mut retval,
+                &mut consumed,
+            );
+            let value = if retval.is_null() {
+                String::new()
+            } else {
+                std::ffi::CStr::from_ptr(retval as *const libc::c_char)
+                    .to_string_lossy()
+                    .into_owned()
+            };
+            return (self.item(item), value);
         }
     }
 
-    /// Prepend to text of item after it has already been created.
-    pub fn prepend_text(self: &mut Self, text: &str) {
-        let to_add = CString::new(text).unwrap().into_raw();
+    /// Add a bytes item, returning a copy of the bytes.
+    pub fn add_item_ret_bytes(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, Vec<u8>) {
+        let item = self.add_item(hfindex, tvb, start, length, encoding);
+        return (item, tvb.get_mem(start, length));
+    }
+
+    /// Add an absolute or relative time item, returning the time. The time is decoded by wireshark, so it matches the
+    /// tree, negative relative times keep their sign.
+    pub fn add_item_ret_time(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, nstime::nstime_t) {
+        let mut retval: nstime::nstime_t = Default::default();
         unsafe {
-            proto::proto_item_prepend_text(self.item.into(), to_add);
-            let _ = CString::from_raw(to_add);
+            let item = proto::proto_tree_add_time_item(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                encoding,
+                &mut retval,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+            );
+            return (self.item(item), retval);
         }
     }
 
-    pub fn add_subtree(self: &mut Self, ett_id: proto::ETTIndex) -> ProtoTree {
-        unsafe { ProtoTree::from_ptr(proto::proto_item_add_subtree(self.item.into(), ett_id)) }
+    /// Add an IPv4 address item, returning the address.
+    pub fn add_item_ret_ipv4(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, std::net::Ipv4Addr) {
+        #[cfg(ws_ge_3_0)]
+        {
+            let mut retval: u32 = 0;
+            unsafe {
+                let item = proto::proto_tree_add_item_ret_ipv4(
+                    self.tree,
+                    hfindex,
+                    tvb.into(),
+                    start as i32,
+                    4,
+                    encoding,
+                    &mut retval,
+                );
+                return (self.item(item), std::net::Ipv4Addr::from(retval.to_ne_bytes()));
+            }
+        }
+        #[cfg(not(ws_ge_3_0))]
+        return self.add_item_decoded(hfindex, tvb, start, 4, encoding, |b: [u8; 4]| {
+            std::net::Ipv4Addr::from(b)
+        });
     }
-}
-/// Struct to represent a Testy Virtual Buffer, serves as a wrapper around the `tvb_*` C functions.
-#[derive(Copy, Clone)]
-pub struct TVB {
-    tvb: *mut tvbuff::tvbuff_t,
-}
-impl TVB {
-    /// Create this structure from a raw pointer.
-    pub unsafe fn from_ptr(tvb: *mut tvbuff::tvbuff_t) -> TVB {
-        return TVB { tvb: tvb };
+
+    /// Add an IPv6 address item, returning the address.
+    pub fn add_item_ret_ipv6(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+    ) -> (ProtoItem, std::net::Ipv6Addr) {
+        let encoding = proto::Encoding::NA;
+        #[cfg(ws_ge_4_0)]
+        {
+            let mut retval: [u8; 16] = [0u8; 16];
+            unsafe {
+                let item = proto::proto_tree_add_item_ret_ipv6(
+                    self.tree,
+                    hfindex,
+                    tvb.into(),
+                    start as i32,
+                    16,
+                    encoding,
+                    &mut retval,
+                );
+                return (self.item(item), std::net::Ipv6Addr::from(retval));
+            }
+        }
+        #[cfg(not(ws_ge_4_0))]
+        return self.add_item_decoded(hfindex, tvb, start, 16, encoding, |b: [u8; 16]| {
+            std::net::Ipv6Addr::from(b)
+        });
     }
 
-    /// Function to create a byte slice that can be used to access the data from the tvb.
-    /// This comes with the following disclaimer in the header:
-    ///
-    /// This function is possibly expensive, temporarily allocating
-    /// another copy of the packet data. Furthermore, it's dangerous because once
-    /// this pointer is given to the user, there's no guarantee that the user will
-    /// honor the 'length' and not overstep the boundaries of the buffer.
-    ///
-    /// If you're thinking of using tvb_get_ptr, STOP WHAT YOU ARE DOING
-    /// IMMEDIATELY. Go take a break. Consider that tvb_get_ptr hands you
-    /// a raw, unprotected pointer that you can easily use to create a
-    /// security vulnerability or otherwise crash Wireshark. Then consider
-    /// that you can probably find a function elsewhere in this file that
-    /// does exactly what you want in a much more safe and robust manner.
-    pub fn tvb_get_ptr(self: &mut Self, offset: usize) -> &[u8] {
-        unsafe {
-            let mut available_length = tvbuff::tvb_reported_length_remaining(self.tvb, offset as i32);
-            if available_length < 0 {
-                available_length = 0;
+    /// Add an ethernet address item, returning the six bytes of the address.
+    pub fn add_item_ret_ether(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+    ) -> (ProtoItem, [u8; 6]) {
+        #[cfg(ws_ge_4_0)]
+        {
+            let mut address = [0u8; 6];
+            unsafe {
+                let item = proto::proto_tree_add_item_ret_ether(
+                    self.tree,
+                    hfindex,
+                    tvb.into(),
+                    start as i32,
+                    6,
+                    proto::Encoding::NA,
+                    address.as_mut_ptr(),
+                );
+                return (self.item(item), address);
             }
-            let data_ptr = tvbuff::tvb_get_ptr(self.tvb, offset as i32, available_length as i32);
-            return std::slice::from_raw_parts(data_ptr, available_length as usize);
+        }
+        #[cfg(not(ws_ge_4_0))]
+        {
+            let item = self.add_item(hfindex, tvb, start, 6, proto::Encoding::NA);
+            let mut address = [0u8; 6];
+            address.copy_from_slice(&tvb.get_mem(start, 6));
+            return (item, address);
+        }
+    }
+
+    /// Add a GUID item, returning the GUID. With the little endian encoding the first three parts of it are little
+    /// endian, like Microsoft's GUIDs.
+    pub fn add_item_ret_guid(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        encoding: proto::Encoding,
+    ) -> (ProtoItem, Guid) {
+        let item = self.add_item(hfindex, tvb, start, 16, encoding);
+        let b = tvb.get_mem(start, 16);
+        let mut guid = Guid {
+            data1: u32::from_be_bytes([b[0], b[1], b[2], b[3]]),
+            data2: u16::from_be_bytes([b[4], b[5]]),
+            data3: u16::from_be_bytes([b[6], b[7]]),
+            data4: [0u8; 8],
         };
+        if encoding.is_little_endian() {
+            guid.data1 = guid.data1.swap_bytes();
+            guid.data2 = guid.data2.swap_bytes();
+            guid.data3 = guid.data3.swap_bytes();
+        }
+        guid.data4.copy_from_slice(&b[8..16]);
+        return (item, guid);
     }
 
-    /// Get reported length of buffer.
-    pub fn reported_length(self: &mut Self) -> usize {
+    /// Add an item with a value from Rust instead of the tvb, the field must be an unsigned integer of up to 32 bits,
+    /// or a frame number. The item covers the range of the tvb it was derived from.
+    pub fn add_uint(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: u32,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, UINT_TYPES)?;
         unsafe {
-            return tvbuff::tvb_reported_length(self.tvb) as usize;
+            let item = proto::proto_tree_add_uint(self.tree, hfindex, tvb.into(), start as i32, length as i32, value);
+            return Ok(self.item(item));
         }
     }
 
-    /// Computes bytes of reported packet data to end of buffer, from offset
-    /// (which can be negative, to indicate bytes from end of buffer). Function
-    /// returns 0 if offset is either at the end of the buffer or out of bounds.
-    /// No exception is thrown.
-    pub fn reported_length_remaining(self: &mut Self, offset: usize) -> i32 {
+    /// Like [`ProtoTree::add_uint`], with the text shown as the value.
+    pub fn add_uint_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: u32,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, UINT_TYPES)?;
+        let text = util::c_string(text);
         unsafe {
-            return tvbuff::tvb_reported_length_remaining(self.tvb, offset as i32);
+            let item = proto::proto_tree_add_uint_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value,
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
         }
     }
 
-    /// Retrieve a block of memory from the buffer.
-    ///
-    /// Does not suffer from possible
-    /// expense of tvb_get_ptr(), since this routine is smart enough
-    /// to copy data in chunks if the request range actually exists in
-    /// different "real" tvbuffs.
-    pub fn get_mem(self: &mut Self, offset: usize, length: usize) -> Vec<u8> {
-        let mut v: Vec<u8> = vec![0; length];
+    /// Add an item with a value from Rust instead of the tvb, the field must be a signed integer of up to 32 bits. The
+    /// item covers the range of the tvb it was derived from.
+    pub fn add_int(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: i32,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, INT_TYPES)?;
         unsafe {
-            tvbuff::tvb_memcpy(self.tvb, v.as_mut_ptr() as *mut libc::c_void, offset as i32, length);
+            let item = proto::proto_tree_add_int(self.tree, hfindex, tvb.into(), start as i32, length as i32, value);
+            return Ok(self.item(item));
         }
-        return v;
     }
-}
 
-impl From<&mut TVB> for *mut tvbuff::tvbuff_t {
-    fn from(field: &mut TVB) -> Self {
-        return field.tvb;
+    /// Like [`ProtoTree::add_int`], with the text shown as the value.
+    pub fn add_int_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: i32,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, INT_TYPES)?;
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_int_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value,
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Add an item with a value from Rust instead of the tvb, the field must be an unsigned integer of 40 up to 64
+    /// bits. The item covers the range of the tvb it was derived from.
+    pub fn add_uint64(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: u64,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, UINT64_TYPES)?;
+        unsafe {
+            let item = proto::proto_tree_add_uint64(self.tree, hfindex, tvb.into(), start as i32, length as i32, value);
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Like [`ProtoTree::add_uint64`], with the text shown as the value.
+    pub fn add_uint64_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: u64,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, UINT64_TYPES)?;
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_uint64_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value,
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Add an item with a value from Rust instead of the tvb, the field must be a signed integer of 40 up to 64 bits.
+    /// The item covers the range of the tvb it was derived from.
+    pub fn add_int64(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: i64,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, INT64_TYPES)?;
+        unsafe {
+            let item = proto::proto_tree_add_int64(self.tree, hfindex, tvb.into(), start as i32, length as i32, value);
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Like [`ProtoTree::add_int64`], with the text shown as the value.
+    pub fn add_int64_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: i64,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, INT64_TYPES)?;
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_int64_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value,
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Add an item with a value from Rust instead of the tvb, the field must be a string. The item covers the range
+    /// of the tvb it was derived from.
+    pub fn add_string(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, STRING_TYPES)?;
+        let value = util::c_string(value);
+        unsafe {
+            let item = proto::proto_tree_add_string(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Like [`ProtoTree::add_string`], with the text shown as the value.
+    pub fn add_string_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: &str,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, STRING_TYPES)?;
+        let value = util::c_string(value);
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_string_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value.as_ptr(),
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Add an item with bytes from Rust instead of the tvb, the field must hold bytes. The item covers as many bytes
+    /// of the tvb as the value holds.
+    pub fn add_bytes(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        value: &[u8],
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, BYTES_TYPES)?;
+        unsafe {
+            let item = proto::proto_tree_add_bytes(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                value.len() as i32,
+                value.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Like [`ProtoTree::add_bytes`], with the text shown as the value.
+    pub fn add_bytes_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        value: &[u8],
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, BYTES_TYPES)?;
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_bytes_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                value.len() as i32,
+                value.as_ptr(),
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Add an item with a value from Rust instead of the tvb, the field must be a double. The item covers the range
+    /// of the tvb it was derived from.
+    pub fn add_double(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: f64,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, &[FieldType::DOUBLE])?;
+        unsafe {
+            let item = proto::proto_tree_add_double(self.tree, hfindex, tvb.into(), start as i32, length as i32, value);
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Like [`ProtoTree::add_double`], with the text shown as the value.
+    pub fn add_double_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: f64,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, &[FieldType::DOUBLE])?;
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_double_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value,
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Add an item with a value from Rust instead of the tvb, the field must be a boolean. The item covers the range
+    /// of the tvb it was derived from.
+    pub fn add_boolean(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: bool,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, &[FieldType::BOOLEAN])?;
+        unsafe {
+            let item = proto::proto_tree_add_boolean(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value as proto::BooleanValue,
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Like [`ProtoTree::add_boolean`], with the text shown as the value.
+    pub fn add_boolean_format_value(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        value: bool,
+        text: &str,
+    ) -> Result<ProtoItem, &'static str> {
+        check_field_type(hfindex, &[FieldType::BOOLEAN])?;
+        let text = util::c_string(text);
+        unsafe {
+            let item = proto::proto_tree_add_boolean_format_value(
+                self.tree,
+                hfindex,
+                tvb.into(),
+                start as i32,
+                length as i32,
+                value as proto::BooleanValue,
+                util::perm_string_ptr("%s"),
+                text.as_ptr(),
+            );
+            return Ok(self.item(item));
+        }
+    }
+
+    /// Adds the item and decodes the bytes it covers, the bytes are passed in big endian order, left aligned. This is
+    /// the fallback for releases that don't have the matching `proto_tree_add_item_ret_` function.
+    #[cfg(not(ws_ge_4_0))]
+    fn add_item_decoded<B, T, F>(
+        self: &mut Self,
+        hfindex: proto::HFIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        encoding: proto::Encoding,
+        decode: F,
+    ) -> (ProtoItem, T)
+    where
+        B: Default + AsMut<[u8]>,
+        F: FnOnce(B) -> T,
+    {
+        let item = self.add_item(hfindex, tvb, start, length, encoding);
+        let data = tvb.get_mem(start, length.min(std::mem::size_of::<B>()));
+        return (item, decode(big_endian_bytes(data, encoding.is_little_endian())));
+    }
+
+    fn item(self: &Self, item: *mut proto::proto_item) -> ProtoItem {
+        return ProtoItem {
+            item,
+            pinfo: self.pinfo,
+        };
+    }
+
+    /// Add an expert info over a range of bytes in the tvb, shown in the tree and the expert information dialog. Without
+    /// text the summary that was registered for the expert info is used. Expert infos are stored in the packet info, so
+    /// without it nothing is added.
+    pub fn add_expert(
+        self: &mut Self,
+        expert: expert::ExpertIndex,
+        tvb: &mut TVB,
+        start: usize,
+        length: usize,
+        text: Option<&str>,
+    ) -> ProtoItem {
+        if self.pinfo.is_null() {
+            return ProtoItem {
+                item: std::ptr::null_mut(),
+                pinfo: self.pinfo,
+            };
+        }
+        let item = unsafe {
+            match text {
+                Some(text) => {
+                    let text = util::c_string(text);
+                    expert::proto_tree_add_expert_format(
+                        self.tree,
+                        self.pinfo,
+                        &expert.0,
+                        tvb.into(),
+                        start as i32,
+                        length as i32,
+                        util::perm_string_ptr("%s"),
+                        text.as_ptr(),
+                    )
+                }
+                None => expert::proto_tree_add_expert(
+                    self.tree,
+                    self.pinfo,
+                    &expert.0,
+                    tvb.into(),
+                    start as i32,
+                    length as i32,
+                ),
+            }
+        };
+        return ProtoItem {
+            item,
+            pinfo: self.pinfo,
+        };
+    }
+
+    /// Function to retrieve all field info's currently associated with the protocol tree.
+    pub fn all_finfos(self: &mut Self) -> Vec<FieldInfo> {
+        let mut res: Vec<FieldInfo> = Vec::new();
+
+        // see wslua_field.c function wslua_all_field_infos
+        if self.tree.is_null()
+        // Not too sure when this happens... tree seems to be null when first invoked?
+        {
+            return res;
+        }
+        unsafe {
+            let fields = proto::proto_all_finfos(self.tree);
+            for i in 0..(*fields).len() {
+                let field =
+                    std::mem::transmute::<*mut libc::c_void, *const proto::field_info>((*fields).index(i as isize));
+                res.push(FieldInfo::from_ptr(field));
+            }
+            glib::g_ptr_array_free(fields, true);
+            // The field info's actually stay in scope, as they are part of the proto datastructure.
+            // the lua part also persists them after they're gone.
+        }
+        return res;
+    }
+}
+
+/// Struct to represent a protocol item, serves as a wrapper around the `proto_item_*` C functions.
+#[derive(Copy, Clone)]
+pub struct ProtoItem {
+    item: *mut proto::proto_item,
+    pinfo: *mut packet_info::packet_info,
+}
+impl From<&mut ProtoItem> for *mut proto::proto_item {
+    fn from(field: &mut ProtoItem) -> Self {
+        return field.item;
+    }
+}
+
+impl ProtoItem {
+    /// Replace text of item after it already has been created.
+    pub fn set_text(self: &mut Self, text: &str) {
+        let text = util::c_string(text);
+        unsafe {
+            proto::proto_item_set_text(self.item.into(), util::perm_string_ptr("%s"), text.as_ptr());
+        }
+    }
+
+    /// Append to text of item after it has already been created.
+    pub fn append_text(self: &mut Self, text: &str) {
+        let text = util::c_string(text);
+        unsafe {
+            proto::proto_item_append_text(self.item.into(), util::perm_string_ptr("%s"), text.as_ptr());
+        }
+    }
+
+    /// Prepend to text of item after it has already been created.
+    pub fn prepend_text(self: &mut Self, text: &str) {
+        let text = util::c_string(text);
+        unsafe {
+            proto::proto_item_prepend_text(self.item.into(), util::perm_string_ptr("%s"), text.as_ptr());
+        }
+    }
+
+    /// Flag this item with an expert info, shown in the expert information dialog. Without text the summary that was
+    /// registered for the expert info is used. Expert infos are stored in the packet info, so without it nothing is
+    /// added.
+    pub fn add_expert(self: &mut Self, expert: expert::ExpertIndex, text: Option<&str>) -> ProtoItem {
+        if self.pinfo.is_null() {
+            return *self;
+        }
+        let item = unsafe {
+            match text {
+                Some(text) => {
+                    let text = util::c_string(text);
+                    expert::expert_add_info_format(
+                        self.pinfo,
+                        self.item,
+                        &expert.0,
+                        util::perm_string_ptr("%s"),
+                        text.as_ptr(),
+                    )
+                }
+                None => expert::expert_add_info(self.pinfo, self.item, &expert.0),
+            }
+        };
+        return ProtoItem {
+            item,
+            pinfo: self.pinfo,
+        };
+    }
+
+    pub fn add_subtree(self: &mut Self, ett_id: proto::ETTIndex) -> ProtoTree {
+        unsafe { ProtoTree::from_ptrs(proto::proto_item_add_subtree(self.item.into(), ett_id), self.pinfo) }
+    }
+}
+/// Struct to represent a Testy Virtual Buffer, serves as a wrapper around the `tvb_*` C functions.
+#[derive(Copy, Clone)]
+pub struct TVB {
+    tvb: *mut tvbuff::tvbuff_t,
+}
+impl TVB {
+    /// Create this structure from a raw pointer.
+    pub unsafe fn from_ptr(tvb: *mut tvbuff::tvbuff_t) -> TVB {
+        return TVB { tvb: tvb };
+    }
+
+    /// Function to create a byte slice that can be used to access the data from the tvb.
+    /// This comes with the following disclaimer in the header:
+    ///
+    /// This function is possibly expensive, temporarily allocating
+    /// another copy of the packet data. Furthermore, it's dangerous because once
+    /// this pointer is given to the user, there's no guarantee that the user will
+    /// honor the 'length' and not overstep the boundaries of the buffer.
+    ///
+    /// If you're thinking of using tvb_get_ptr, STOP WHAT YOU ARE DOING
+    /// IMMEDIATELY. Go take a break. Consider that tvb_get_ptr hands you
+    /// a raw, unprotected pointer that you can easily use to create a
+    /// security vulnerability or otherwise crash Wireshark. Then consider
+    /// that you can probably find a function elsewhere in this file that
+    /// does exactly what you want in a much more safe and robust manner.
+    pub fn tvb_get_ptr(self: &mut Self, offset: usize) -> &[u8] {
+        unsafe {
+            let mut available_length = tvbuff::tvb_reported_length_remaining(self.tvb, offset as i32);
+            if available_length < 0 {
+                available_length = 0;
+            }
+            let data_ptr = tvbuff::tvb_get_ptr(self.tvb, offset as i32, available_length as i32);
+            return std::slice::from_raw_parts(data_ptr, available_length as usize);
+        };
+    }
+
+    /// Get reported length of buffer.
+    pub fn reported_length(self: &mut Self) -> usize {
+        unsafe {
+            return tvbuff::tvb_reported_length(self.tvb) as usize;
+        }
+    }
+
+    /// Computes bytes of reported packet data to end of buffer, from offset
+    /// (which can be negative, to indicate bytes from end of buffer). Function
+    /// returns 0 if offset is either at the end of the buffer or out of bounds.
+    /// No exception is thrown.
+    pub fn reported_length_remaining(self: &mut Self, offset: usize) -> i32 {
+        unsafe {
+            return tvbuff::tvb_reported_length_remaining(self.tvb, offset as i32);
+        }
+    }
+
+    /// Create a new buffer holding the data from offset to the end of this buffer, to hand to another dissector.
+    pub fn new_subset_remaining(self: &mut Self, offset: usize) -> TVB {
+        unsafe {
+            return TVB::from_ptr(tvbuff::tvb_new_subset_remaining(self.tvb, offset as i32));
+        }
+    }
+
+    /// Retrieve a block of memory from the buffer.
+    ///
+    /// Does not suffer from possible
+    /// expense of tvb_get_ptr(), since this routine is smart enough
+    /// to copy data in chunks if the request range actually exists in
+    /// different "real" tvbuffs.
+    pub fn get_mem(self: &mut Self, offset: usize, length: usize) -> Vec<u8> {
+        let mut v: Vec<u8> = vec![0; length];
+        unsafe {
+            tvbuff::tvb_memcpy(self.tvb, v.as_mut_ptr() as *mut libc::c_void, offset as i32, length);
+        }
+        return v;
+    }
+}
+
+impl From<&mut TVB> for *mut tvbuff::tvbuff_t {
+    fn from(field: &mut TVB) -> Self {
+        return field.tvb;
+    }
+}
+
+/// A dissector table, other dissectors register themselves in it for a key, see
+/// [`Dissector::get_dissector_tables()`](crate::dissector::Dissector::get_dissector_tables).
+#[derive(Copy, Clone, Debug)]
+pub struct DissectorTable {
+    table: packet::dissector_table_t,
+}
+// The table is created during registration and lives for the remainder of the program.
+unsafe impl Send for DissectorTable {}
+unsafe impl Sync for DissectorTable {}
+
+impl DissectorTable {
+    /// Create this structure from a raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// The table must be non-null and come from `register_dissector_table` or `find_dissector_table`. Wireshark keeps
+    /// the registered tables until it exits, so the result may be stored and used in later dissections.
+    pub unsafe fn from_ptr(table: packet::dissector_table_t) -> DissectorTable {
+        return DissectorTable { table };
+    }
+
+    /// Find a dissector table by its name, like `ethertype` or `udp.port`.
+    pub fn find(name: &str) -> Option<DissectorTable> {
+        let name = util::c_string(name);
+        let table = unsafe { packet::find_dissector_table(name.as_ptr()) };
+        if table.is_null() {
+            return None;
+        }
+        return Some(DissectorTable { table });
+    }
+
+    /// Call the dissector registered for this integer key on the tvb, returns how many bytes were used, this is zero if
+    /// no dissector is registered for the key or it rejected the data. The tree must have been created from the
+    /// pointers the dissector was called with, as the packet info is needed, without it nothing is called.
+    pub fn try_uint(self: &Self, key: u32, tvb: &mut TVB, tree: &mut ProtoTree) -> usize {
+        if tree.pinfo.is_null() {
+            return 0;
+        }
+        unsafe {
+            return packet::dissector_try_uint(self.table, key, tvb.into(), tree.pinfo, tree.tree) as usize;
+        }
+    }
+
+    /// Call the dissector registered for this string key on the tvb, see [`DissectorTable::try_uint()`].
+    pub fn try_string(self: &Self, key: &str, tvb: &mut TVB, tree: &mut ProtoTree) -> usize {
+        if tree.pinfo.is_null() {
+            return 0;
+        }
+        // C strings end at the first nul, so that's where the key ends.
+        let key = util::c_string(key);
+        unsafe {
+            return packet::dissector_try_string(
+                self.table,
+                key.as_ptr(),
+                tvb.into(),
+                tree.pinfo,
+                tree.tree,
+                std::ptr::null_mut(),
+            ) as usize;
+        }
+    }
+
+    /// Call the dissector registered for this integer key on the tvb, if there is none the data is shown as bytes.
+    /// Returns how many bytes were used.
+    pub fn dissect_uint(self: &Self, key: u32, tvb: &mut TVB, tree: &mut ProtoTree) -> usize {
+        let used = self.try_uint(key, tvb, tree);
+        if used != 0 {
+            return used;
+        }
+        return call_data_dissector(tvb, tree);
+    }
+
+    /// Call the dissector registered for this string key on the tvb, if there is none the data is shown as bytes.
+    /// Returns how many bytes were used.
+    pub fn dissect_string(self: &Self, key: &str, tvb: &mut TVB, tree: &mut ProtoTree) -> usize {
+        let used = self.try_string(key, tvb, tree);
+        if used != 0 {
+            return used;
+        }
+        return call_data_dissector(tvb, tree);
+    }
+}
+
+/// A handle to a dissector, used to call it directly.
+#[derive(Copy, Clone, Debug)]
+pub struct DissectorHandle {
+    handle: packet::dissector_handle_t,
+}
+// Handles are created during registration and live for the remainder of the program.
+unsafe impl Send for DissectorHandle {}
+unsafe impl Sync for DissectorHandle {}
+
+impl DissectorHandle {
+    /// Create this structure from a raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// The handle must be non-null and come from `create_dissector_handle`, `register_dissector` or `find_dissector`.
+    /// Wireshark never frees dissector handles, so the result may be stored and used in later dissections.
+    pub unsafe fn from_ptr(handle: packet::dissector_handle_t) -> DissectorHandle {
+        return DissectorHandle { handle };
+    }
+
+    /// Find a dissector by the name it was registered with, like `eth_withoutfcs`, `ip` or `json`.
+    pub fn find(name: &str) -> Option<DissectorHandle> {
+        let name = util::c_string(name);
+        let handle = unsafe { packet::find_dissector(name.as_ptr()) };
+        if handle.is_null() {
+            return None;
+        }
+        return Some(DissectorHandle { handle });
+    }
+
+    /// Call the dissector on the tvb, returns how many bytes it used. The tree must have been created from the pointers
+    /// the dissector was called with, as the packet info is needed, without it nothing is called.
+    pub fn call(self: &Self, tvb: &mut TVB, tree: &mut ProtoTree) -> usize {
+        if tree.pinfo.is_null() {
+            return 0;
+        }
+        unsafe {
+            return packet::call_dissector(self.handle, tvb.into(), tree.pinfo, tree.tree) as usize;
+        }
+    }
+
+    /// Call the dissector on the tvb, passing it data, what that data must be depends on the dissector called.
+    ///
+    /// # Safety
+    ///
+    /// The data is passed on as the `void *data` argument of the called dissector, which casts it to whatever type it
+    /// expects without any check. It must be null or point to that type, for example a `struct tcpinfo` for dissectors
+    /// of the `tcp.port` table, and stay valid until the call returns.
+    pub unsafe fn call_with_data(self: &Self, tvb: &mut TVB, tree: &mut ProtoTree, data: *mut libc::c_void) -> usize {
+        if tree.pinfo.is_null() {
+            return 0;
+        }
+        return packet::call_dissector_with_data(self.handle, tvb.into(), tree.pinfo, tree.tree, data) as usize;
+    }
+}
+
+impl From<DissectorHandle> for packet::dissector_handle_t {
+    fn from(handle: DissectorHandle) -> Self {
+        return handle.handle;
+    }
+}
+
+/// Show the tvb as plain bytes in the tree, returns how many bytes were used. Like other dissectors this needs the
+/// packet info, without it nothing is shown.
+pub fn call_data_dissector(tvb: &mut TVB, tree: &mut ProtoTree) -> usize {
+    if tree.pinfo.is_null() {
+        return 0;
+    }
+    unsafe {
+        return packet::call_data_dissector(tvb.into(), tree.pinfo, tree.tree) as usize;
+    }
+}
+
+impl From<DissectorTable> for packet::dissector_table_t {
+    fn from(table: DissectorTable) -> Self {
+        return table.table;
+    }
+}
+
+/// A list of heuristic dissectors, other dissectors add themselves to it with [`Registration::Heuristic`] to be tried on
+/// the payload, see [`Dissector::get_heuristic_lists()`].
+///
+/// [`Registration::Heuristic`]: crate::dissector::Registration::Heuristic
+/// [`Dissector::get_heuristic_lists()`]: crate::dissector::Dissector::get_heuristic_lists
+#[derive(Copy, Clone, Debug)]
+pub struct HeuristicList {
+    list: packet::heur_dissector_list_t,
+}
+// The list is created during registration and lives for the remainder of the program.
+unsafe impl Send for HeuristicList {}
+unsafe impl Sync for HeuristicList {}
+
+impl HeuristicList {
+    /// Create this structure from a raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// The list must be non-null and come from `register_heur_dissector_list` or `find_heur_dissector_list`,
+    /// `dissector_try_heuristic` walks its entries without checking it. Registered lists live until wireshark exits, so
+    /// the result may be stored.
+    pub unsafe fn from_ptr(list: packet::heur_dissector_list_t) -> HeuristicList {
+        return HeuristicList { list };
+    }
+
+    /// Try the heuristic dissectors in this list on the tvb, returns whether one of them claimed it. The tree must have
+    /// been created from the pointers the dissector was called with, as the packet info is needed, without it nothing
+    /// is tried.
+    pub fn try_dissect(self: &Self, tvb: &mut TVB, tree: &mut ProtoTree) -> bool {
+        if tree.pinfo.is_null() {
+            return false;
+        }
+        let mut entry: *mut packet::heur_dtbl_entry_t = std::ptr::null_mut();
+        unsafe {
+            return packet::dissector_try_heuristic(
+                self.list,
+                tvb.into(),
+                tree.pinfo,
+                tree.tree,
+                &mut entry,
+                std::ptr::null_mut(),
+            );
+        }
+    }
+}
+
+/// The storage wireshark writes a preference's value to, it's leaked as wireshark holds on to it for the remainder of
+/// the program.
+#[derive(Copy, Clone, Debug)]
+enum PreferenceStorage {
+    Bool(*mut prefs::BoolPreference),
+    UInt(*mut u32),
+    Enum(*mut i32),
+    String(*mut *const libc::c_char),
+    Range(*mut *mut range::epan_range),
+}
+
+/// A registered preference, this reads the value the user currently has set, see [`PrefsModule`] for registration.
+/// Wireshark changes the values from its main thread when preferences are applied.
+#[derive(Copy, Clone, Debug)]
+pub struct Preference {
+    storage: PreferenceStorage,
+}
+// The storage lives for the remainder of the program.
+unsafe impl Send for Preference {}
+unsafe impl Sync for Preference {}
+
+impl Preference {
+    /// The value of a bool preference, None for other preferences.
+    pub fn bool(self: &Self) -> Option<bool> {
+        match self.storage {
+            PreferenceStorage::Bool(v) => unsafe { Some(*v != prefs::BoolPreference::from(false)) },
+            _ => None,
+        }
+    }
+
+    /// The value of an uint preference, None for other preferences.
+    pub fn uint(self: &Self) -> Option<u32> {
+        match self.storage {
+            PreferenceStorage::UInt(v) => unsafe { Some(*v) },
+            _ => None,
+        }
+    }
+
+    /// The value of an enum preference, this is the value of the selected entry, None for other preferences.
+    pub fn enum_value(self: &Self) -> Option<i32> {
+        match self.storage {
+            PreferenceStorage::Enum(v) => unsafe { Some(*v) },
+            _ => None,
+        }
+    }
+
+    /// The value of a string or filename preference, None for other preferences.
+    pub fn string(self: &Self) -> Option<String> {
+        match self.storage {
+            PreferenceStorage::String(v) => unsafe {
+                if (*v).is_null() {
+                    return Some(String::new());
+                }
+                Some(std::ffi::CStr::from_ptr(*v).to_string_lossy().into_owned())
+            },
+            _ => None,
+        }
+    }
+
+    /// The inclusive (low, high) ranges of a range preference, None for other preferences.
+    pub fn ranges(self: &Self) -> Option<Vec<(u32, u32)>> {
+        match self.storage {
+            PreferenceStorage::Range(v) => unsafe {
+                let range = *v;
+                if range.is_null() {
+                    return Some(vec![]);
+                }
+                // The ranges are a flexible array member, only nranges of them exist.
+                let entries = std::ptr::addr_of!((*range).ranges) as *const range::range_admin_t;
+                Some(
+                    (0..(*range).nranges as usize)
+                        .map(|i| {
+                            let entry = *entries.add(i);
+                            (entry.low, entry.high)
+                        })
+                        .collect(),
+                )
+            },
+            _ => None,
+        }
+    }
+}
+
+/// The preferences of a protocol, serves as a wrapper around the `prefs_register_*` C functions.
+#[derive(Copy, Clone, Debug)]
+pub struct PrefsModule {
+    module: *mut prefs::module_t,
+}
+
+impl PrefsModule {
+    /// Create this structure from a raw pointer.
+    ///
+    /// # Safety
+    ///
+    /// The module must be non-null and be the one `prefs_register_protocol` returned for the plugin's own protocol,
+    /// preferences registered on it are shown under that protocol. Wireshark writes to the registered values when
+    /// preferences are applied, so the module must only be used during registration, from wireshark's main thread.
+    pub unsafe fn from_ptr(module: *mut prefs::module_t) -> PrefsModule {
+        return PrefsModule { module };
+    }
+
+    /// Register a checkbox.
+    pub fn register_bool(self: &mut Self, name: &str, title: &str, description: &str, default: bool) -> Preference {
+        let var = Box::leak(Box::new(prefs::BoolPreference::from(default)));
+        unsafe {
+            prefs::prefs_register_bool_preference(
+                self.module,
+                util::perm_string_ptr(name),
+                util::perm_string_ptr(title),
+                util::perm_string_ptr(description),
+                var,
+            );
+        }
+        return Preference {
+            storage: PreferenceStorage::Bool(var),
+        };
+    }
+
+    /// Register an unsigned integer, base is the base it's shown and entered in, like 10 or 16.
+    pub fn register_uint(
+        self: &mut Self,
+        name: &str,
+        title: &str,
+        description: &str,
+        base: u32,
+        default: u32,
+    ) -> Preference {
+        let var = Box::leak(Box::new(default));
+        unsafe {
+            prefs::prefs_register_uint_preference(
+                self.module,
+                util::perm_string_ptr(name),
+                util::perm_string_ptr(title),
+                util::perm_string_ptr(description),
+                base,
+                var,
+            );
+        }
+        return Preference {
+            storage: PreferenceStorage::UInt(var),
+        };
+    }
+
+    /// Register a choice between the (name, description, value) entries in values, shown as radio buttons or a
+    /// dropdown.
+    pub fn register_enum(
+        self: &mut Self,
+        name: &str,
+        title: &str,
+        description: &str,
+        values: &[(&str, &str, i32)],
+        radio_buttons: bool,
+        default: i32,
+    ) -> Preference {
+        let var = Box::leak(Box::new(default));
+        let mut enumvals: Vec<prefs::enum_val_t> = values
+            .iter()
+            .map(|(name, description, value)| prefs::enum_val_t {
+                name: util::perm_string_ptr(name),
+                description: util::perm_string_ptr(description),
+                value: *value,
+            })
+            .collect();
+        enumvals.push(prefs::enum_val_t {
+            name: std::ptr::null(),
+            description: std::ptr::null(),
+            value: 0,
+        });
+        let enumvals = Box::leak(enumvals.into_boxed_slice());
+        unsafe {
+            prefs::prefs_register_enum_preference(
+                self.module,
+                util::perm_string_ptr(name),
+                util::perm_string_ptr(title),
+                util::perm_string_ptr(description),
+                var,
+                enumvals.as_ptr(),
+                radio_buttons as i32,
+            );
+        }
+        return Preference {
+            storage: PreferenceStorage::Enum(var),
+        };
+    }
+
+    /// Register a text field.
+    pub fn register_string(self: &mut Self, name: &str, title: &str, description: &str, default: &str) -> Preference {
+        let var = Box::leak(Box::new(util::perm_string_ptr(default)));
+        unsafe {
+            prefs::prefs_register_string_preference(
+                self.module,
+                util::perm_string_ptr(name),
+                util::perm_string_ptr(title),
+                util::perm_string_ptr(description),
+                var,
+            );
+        }
+        return Preference {
+            storage: PreferenceStorage::String(var),
+        };
+    }
+
+    /// Register a file path, for_writing selects whether the file dialog asks for a file to save to or to open.
+    pub fn register_filename(
+        self: &mut Self,
+        name: &str,
+        title: &str,
+        description: &str,
+        default: &str,
+        for_writing: bool,
+    ) -> Preference {
+        let var = Box::leak(Box::new(util::perm_string_ptr(default)));
+        unsafe {
+            prefs::prefs_register_filename_preference(
+                self.module,
+                util::perm_string_ptr(name),
+                util::perm_string_ptr(title),
+                util::perm_string_ptr(description),
+                var,
+                for_writing as i32,
+            );
+        }
+        return Preference {
+            storage: PreferenceStorage::String(var),
+        };
+    }
+
+    /// Register a range of integers, written like "1-5,7", values above max_value are rejected. A default that doesn't
+    /// parse results in an empty range.
+    pub fn register_range(
+        self: &mut Self,
+        name: &str,
+        title: &str,
+        description: &str,
+        default: &str,
+        max_value: u32,
+    ) -> Preference {
+        let var: &mut *mut range::epan_range = Box::leak(Box::new(std::ptr::null_mut()));
+        unsafe {
+            let scope = wmem::wmem_epan_scope();
+            let parsed = range::range_convert_str(scope, var, util::perm_string_ptr(default), max_value);
+            if parsed != range::convert_ret_t::CVT_NO_ERROR {
+                range::range_convert_str(scope, var, util::perm_string_ptr(""), max_value);
+            }
+            prefs::prefs_register_range_preference(
+                self.module,
+                util::perm_string_ptr(name),
+                util::perm_string_ptr(title),
+                util::perm_string_ptr(description),
+                var,
+                max_value,
+            );
+        }
+        return Preference {
+            storage: PreferenceStorage::Range(var),
+        };
+    }
+}
+
+/// A GUID, as returned by [`ProtoTree::add_item_ret_guid`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Guid {
+    pub data1: u32,
+    pub data2: u16,
+    pub data3: u16,
+    pub data4: [u8; 8],
+}
+
+/// The type of an [`Address`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AddressType {
+    NONE,
+    ETHER,
+    IPv4,
+    IPv6,
+    /// Any of the other address types, with the value wireshark uses for it.
+    Other(i32),
+}
+
+/// A copy of an address from the packet info.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    pub address_type: AddressType,
+    pub data: Vec<u8>,
+}
+
+impl Address {
+    unsafe fn from_ptr(addr: *const address::address) -> Address {
+        let address_type = match (*addr).type_ {
+            address::AT_NONE => AddressType::NONE,
+            address::AT_ETHER => AddressType::ETHER,
+            address::AT_IPv4 => AddressType::IPv4,
+            address::AT_IPv6 => AddressType::IPv6,
+            v => AddressType::Other(v),
+        };
+        let data = if (*addr).data.is_null() || (*addr).len <= 0 {
+            vec![]
+        } else {
+            std::slice::from_raw_parts((*addr).data as *const u8, (*addr).len as usize).to_vec()
+        };
+        return Address { address_type, data };
+    }
+
+    /// The IP address, if this is one.
+    pub fn ip(self: &Self) -> Option<std::net::IpAddr> {
+        match self.address_type {
+            AddressType::IPv4 if self.data.len() == 4 => {
+                let octets: [u8; 4] = std::convert::TryInto::try_into(&self.data[..]).ok()?;
+                Some(std::net::IpAddr::from(octets))
+            }
+            AddressType::IPv6 if self.data.len() == 16 => {
+                let octets: [u8; 16] = std::convert::TryInto::try_into(&self.data[..]).ok()?;
+                Some(std::net::IpAddr::from(octets))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Direction of a packet in its conversation. Like the TCP dissector does, this compares the source and destination
+/// addresses, and the ports if those are equal, so both directions of a conversation always get a different one.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    /// The source is the lower of the two endpoints.
+    Forward,
+    /// The source is the higher of the two endpoints.
+    Backward,
+}
+
+impl Direction {
+    /// The direction from the source to the destination endpoint.
+    fn between(src: Address, src_port: u32, dst: Address, dst_port: u32) -> Direction {
+        let src_key = (address_type_value(src.address_type), src.data, src_port);
+        let dst_key = (address_type_value(dst.address_type), dst.data, dst_port);
+        if src_key <= dst_key {
+            return Direction::Forward;
+        }
+        return Direction::Backward;
+    }
+}
+
+/// The columns of the packet list. The numbering of these differs between wireshark releases, so they're looked up
+/// by the format string wireshark uses for them in the preferences.
+#[allow(non_camel_case_types)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Column {
+    /// Packet list item number.
+    NUMBER,
+    /// Time, in the format chosen in the view menu.
+    CLS_TIME,
+    ABS_TIME,
+    ABS_YMD_TIME,
+    REL_TIME,
+    UTC_TIME,
+    DELTA_TIME,
+    /// Source address, from the lowest layer that sets it.
+    DEF_SRC,
+    RES_SRC,
+    UNRES_SRC,
+    DEF_SRC_PORT,
+    /// Destination address, from the lowest layer that sets it.
+    DEF_DST,
+    RES_DST,
+    UNRES_DST,
+    DEF_DST_PORT,
+    DEF_DL_SRC,
+    DEF_DL_DST,
+    DEF_NET_SRC,
+    DEF_NET_DST,
+    /// The name of the protocol.
+    PROTOCOL,
+    /// Summary of the packet.
+    INFO,
+    PACKET_LENGTH,
+    CUMULATIVE_BYTES,
+    EXPERT,
+    VLAN_ID,
+}
+
+impl Column {
+    /// All the columns, in the order they're declared in.
+    const ALL: [Column; 25] = [
+        Column::NUMBER,
+        Column::CLS_TIME,
+        Column::ABS_TIME,
+        Column::ABS_YMD_TIME,
+        Column::REL_TIME,
+        Column::UTC_TIME,
+        Column::DELTA_TIME,
+        Column::DEF_SRC,
+        Column::RES_SRC,
+        Column::UNRES_SRC,
+        Column::DEF_SRC_PORT,
+        Column::DEF_DST,
+        Column::RES_DST,
+        Column::UNRES_DST,
+        Column::DEF_DST_PORT,
+        Column::DEF_DL_SRC,
+        Column::DEF_DL_DST,
+        Column::DEF_NET_SRC,
+        Column::DEF_NET_DST,
+        Column::PROTOCOL,
+        Column::INFO,
+        Column::PACKET_LENGTH,
+        Column::CUMULATIVE_BYTES,
+        Column::EXPERT,
+        Column::VLAN_ID,
+    ];
+
+    /// The id wireshark uses for the column, or -1 if the running release doesn't have it. The ids are looked up on
+    /// first use.
+    fn id(self: &Self) -> i32 {
+        static IDS: std::sync::OnceLock<Vec<i32>> = std::sync::OnceLock::new();
+        let ids = IDS.get_or_init(|| {
+            return Column::ALL
+                .iter()
+                .map(|column| unsafe {
+                    column::get_column_format_from_str(column.format().as_ptr() as *const libc::c_char)
+                })
+                .collect();
+        });
+        return ids[*self as usize];
+    }
+
+    /// Nul terminated format string of the column.
+    fn format(self: &Self) -> &'static str {
+        match self {
+            Column::NUMBER => "%m\0",
+            Column::CLS_TIME => "%t\0",
+            Column::ABS_TIME => "%At\0",
+            Column::ABS_YMD_TIME => "%Yt\0",
+            Column::REL_TIME => "%Rt\0",
+            Column::UTC_TIME => "%Aut\0",
+            Column::DELTA_TIME => "%Tt\0",
+            Column::DEF_SRC => "%s\0",
+            Column::RES_SRC => "%rs\0",
+            Column::UNRES_SRC => "%us\0",
+            Column::DEF_SRC_PORT => "%S\0",
+            Column::DEF_DST => "%d\0",
+            Column::RES_DST => "%rd\0",
+            Column::UNRES_DST => "%ud\0",
+            Column::DEF_DST_PORT => "%D\0",
+            Column::DEF_DL_SRC => "%hs\0",
+            Column::DEF_DL_DST => "%hd\0",
+            Column::DEF_NET_SRC => "%ns\0",
+            Column::DEF_NET_DST => "%nd\0",
+            Column::PROTOCOL => "%p\0",
+            Column::INFO => "%i\0",
+            Column::PACKET_LENGTH => "%L\0",
+            Column::CUMULATIVE_BYTES => "%B\0",
+            Column::EXPERT => "%a\0",
+            Column::VLAN_ID => "%q\0",
+        }
+    }
+}
+
+/// Struct to represent the packet info, the information about the packet being dissected.
+#[derive(Copy, Clone)]
+pub struct PacketInfo {
+    pinfo: *mut packet_info::packet_info,
+    proto_id: i32,
+}
+
+impl PacketInfo {
+    /// Create this structure from a raw pointer, without a protocol the proto data can't be used.
+    ///
+    /// # Safety
+    ///
+    /// The packet info must be non-null and be the one wireshark passed to the running dissector. It belongs to the
+    /// packet being dissected and wireshark reuses it for the next one, the result must not be used after the dissector
+    /// returns.
+    pub unsafe fn from_ptr(pinfo: *mut packet_info::packet_info) -> PacketInfo {
+        return PacketInfo::with_proto_id(pinfo, -1);
+    }
+
+    /// Create this structure from a raw pointer, the proto data is stored for the protocol with this id.
+    ///
+    /// # Safety
+    ///
+    /// The packet info must be as for [`PacketInfo::from_ptr`]. The proto data is stored under the protocol id, so it
+    /// must be the id wireshark returned when registering the dissector's protocol, or -1 to disable the proto data.
+    pub unsafe fn with_proto_id(pinfo: *mut packet_info::packet_info, proto_id: i32) -> PacketInfo {
+        return PacketInfo { pinfo, proto_id };
+    }
+
+    /// The number of the frame in the capture, starting at 1.
+    pub fn frame_number(self: &Self) -> u32 {
+        unsafe {
+            return (*self.pinfo).num;
+        }
+    }
+
+    /// Whether this packet was dissected before, wireshark dissects all packets once when it loads a file, and again
+    /// whenever they're shown. State should only be built up when this is false.
+    pub fn visited(self: &Self) -> bool {
+        unsafe {
+            let fd = (*self.pinfo).fd;
+            return !fd.is_null() && (*fd).visited();
+        }
+    }
+
+    /// The time at which the packet was captured.
+    pub fn abs_ts(self: &Self) -> nstime::nstime_t {
+        unsafe {
+            return (*self.pinfo).abs_ts;
+        }
+    }
+
+    /// The time since the first packet of the capture.
+    pub fn rel_ts(self: &Self) -> nstime::nstime_t {
+        unsafe {
+            return (*self.pinfo).rel_ts;
+        }
+    }
+
+    /// The source address, from the lowest layer that sets it, usually the network layer.
+    pub fn src(self: &Self) -> Address {
+        unsafe {
+            return Address::from_ptr(&(*self.pinfo).src);
+        }
+    }
+
+    /// The destination address, from the lowest layer that sets it, usually the network layer.
+    pub fn dst(self: &Self) -> Address {
+        unsafe {
+            return Address::from_ptr(&(*self.pinfo).dst);
+        }
+    }
+
+    /// The source port, set by the transport layer.
+    pub fn src_port(self: &Self) -> u32 {
+        unsafe {
+            return (*self.pinfo).srcport;
+        }
+    }
+
+    /// The destination port, set by the transport layer.
+    pub fn dst_port(self: &Self) -> u32 {
+        unsafe {
+            return (*self.pinfo).destport;
+        }
+    }
+
+    /// The value the dissector table matched on to call this dissector, like the port.
+    pub fn match_uint(self: &Self) -> u32 {
+        unsafe {
+            return (*self.pinfo).match_uint;
+        }
+    }
+
+    /// The direction of this packet in its conversation.
+    pub fn direction(self: &Self) -> Direction {
+        return Direction::between(self.src(), self.src_port(), self.dst(), self.dst_port());
+    }
+
+    fn column(self: &Self, column: Column) -> Option<(*mut column_utils::column_info, i32)> {
+        unsafe {
+            let cinfo = (*self.pinfo).cinfo;
+            let index = column.id();
+            if cinfo.is_null() || index < 0 {
+                return None;
+            }
+            return Some((cinfo, index));
+        }
+    }
+
+    /// Store a value against this frame under the key, such that it can be retrieved when the frame is dissected
+    /// again. Use this while the packet isn't [`PacketInfo::visited`], the values are only freed when the capture file
+    /// is closed, adding a value for a key again hides the earlier one.
+    pub fn add_proto_data<T: 'static>(self: &mut Self, key: u32, value: T) {
+        if self.proto_id < 0 {
+            return;
+        }
+        // Boxed twice, the pointer to the Any box is thin so it fits in the void pointer.
+        let data: *mut Box<dyn std::any::Any> = Box::into_raw(Box::new(Box::new(value)));
+        unsafe {
+            proto_data::p_add_proto_data(
+                wmem::wmem_file_scope(),
+                self.pinfo,
+                self.proto_id,
+                key,
+                data as *mut libc::c_void,
+            );
+            drop_with_file_scope(data);
+        }
+    }
+
+    /// Retrieve the value stored against this frame under the key, None if there is none or it's of another type.
+    pub fn get_proto_data<T: 'static>(self: &Self, key: u32) -> Option<&T> {
+        if self.proto_id < 0 {
+            return None;
+        }
+        unsafe {
+            let data = proto_data::p_get_proto_data(wmem::wmem_file_scope(), self.pinfo, self.proto_id, key)
+                as *const Box<dyn std::any::Any>;
+            if data.is_null() {
+                return None;
+            }
+            return (*data).downcast_ref::<T>();
+        }
+    }
+
+    /// Set the column to this text, cheaper than [`PacketInfo::col_add_str`] because wireshark keeps a pointer to the
+    /// string instead of copying it. That needs a nul terminated string, like `"TCP\0"`, other strings are copied as
+    /// by [`PacketInfo::col_add_str`].
+    pub fn col_set_str(self: &mut Self, column: Column, text: &'static str) {
+        if !text.ends_with('\0') {
+            return self.col_add_str(column, text);
+        }
+        if let Some((cinfo, index)) = self.column(column) {
+            unsafe {
+                column_utils::col_set_str(cinfo, index, text.as_ptr() as *const libc::c_char);
+            }
+        }
+    }
+
+    /// Set the column to this text.
+    pub fn col_add_str(self: &mut Self, column: Column, text: &str) {
+        if let Some((cinfo, index)) = self.column(column) {
+            let text = util::c_string(text);
+            unsafe {
+                column_utils::col_add_str(cinfo, index, text.as_ptr());
+            }
+        }
+    }
+
+    /// Set the column to the formatted text, use with [`format_args!`].
+    pub fn col_add_fstr(self: &mut Self, column: Column, args: std::fmt::Arguments) {
+        self.col_add_str(column, &std::fmt::format(args));
+    }
+
+    /// Append this text to the column.
+    pub fn col_append_str(self: &mut Self, column: Column, text: &str) {
+        if let Some((cinfo, index)) = self.column(column) {
+            let text = util::c_string(text);
+            unsafe {
+                column_utils::col_append_str(cinfo, index, text.as_ptr());
+            }
+        }
+    }
+
+    /// Append the formatted text to the column, use with [`format_args!`].
+    pub fn col_append_fstr(self: &mut Self, column: Column, args: std::fmt::Arguments) {
+        self.col_append_str(column, &std::fmt::format(args));
+    }
+
+    /// Append the separator and the text to the column, the separator is left out if the column is still empty.
+    pub fn col_append_sep_str(self: &mut Self, column: Column, separator: &str, text: &str) {
+        if let Some((cinfo, index)) = self.column(column) {
+            let separator = util::c_string(separator);
+            let text = util::c_string(text);
+            unsafe {
+                column_utils::col_append_sep_str(cinfo, index, separator.as_ptr(), text.as_ptr());
+            }
+        }
+    }
+
+    /// Append the separator and the formatted text to the column, use with [`format_args!`].
+    pub fn col_append_sep_fstr(self: &mut Self, column: Column, separator: &str, args: std::fmt::Arguments) {
+        self.col_append_sep_str(column, separator, &std::fmt::format(args));
+    }
+
+    /// Clear the column, up to the fence if there is one.
+    pub fn col_clear(self: &mut Self, column: Column) {
+        if let Some((cinfo, index)) = self.column(column) {
+            unsafe {
+                column_utils::col_clear(cinfo, index);
+            }
+        }
+    }
+
+    /// Put a fence after the current text of the column, setting, adding and clearing only affect what comes after
+    /// it, appending still works. Use this before calling another dissector to keep what this one wrote.
+    pub fn col_set_fence(self: &mut Self, column: Column) {
+        if let Some((cinfo, index)) = self.column(column) {
+            unsafe {
+                column_utils::col_set_fence(cinfo, index);
+            }
+        }
+    }
+}
+
+impl From<&mut PacketInfo> for *mut packet_info::packet_info {
+    fn from(pinfo: &mut PacketInfo) -> Self {
+        return pinfo.pinfo;
+    }
+}
+
+fn address_type_value(address_type: AddressType) -> i32 {
+    match address_type {
+        AddressType::NONE => address::AT_NONE,
+        AddressType::ETHER => address::AT_ETHER,
+        AddressType::IPv4 => address::AT_IPv4,
+        AddressType::IPv6 => address::AT_IPv6,
+        AddressType::Other(v) => v,
+    }
+}
+
+/// Access to the state a dissector keeps for the conversation the packet belongs to, the state is created with
+/// [`Default`] the first time it is used in a conversation. It is freed when the capture file is closed.
+///
+/// The state is shared by all packets of the conversation, and packets are dissected again whenever they're shown.
+/// Use [`PacketInfo::visited`] to only update the state the first time a packet is dissected.
+///
+/// More than one of these can exist for the same conversation, for example in a closure passed to
+/// [`tcp_dissect_pdus`], so the state is kept in a [`RefCell`](std::cell::RefCell) and only one borrow of it can be
+/// held at a time, [`Conversation::state`] returns an error while it's borrowed.
+pub struct Conversation<S: Default + 'static> {
+    pinfo: *mut packet_info::packet_info,
+    proto_id: i32,
+    state: *mut std::cell::RefCell<S>,
+}
+
+impl<S: Default + 'static> Conversation<S> {
+    /// Create this structure for the packet info and the protocol that owns the state.
+    ///
+    /// # Safety
+    ///
+    /// The packet info must be as for [`PacketInfo::from_ptr`]. The state is stored under the protocol id and cast
+    /// back to `S` without any check, so every [`Conversation`] created for that protocol must use the same type `S`.
+    pub unsafe fn from_ptr(pinfo: *mut packet_info::packet_info, proto_id: i32) -> Conversation<S> {
+        return Conversation {
+            pinfo,
+            proto_id,
+            state: std::ptr::null_mut(),
+        };
+    }
+
+    /// The state for the conversation of this packet, this finds or creates the conversation on the first call. Fails
+    /// if the state is still borrowed, through this or another [`Conversation`] for the same conversation.
+    pub fn state(self: &mut Self) -> Result<std::cell::RefMut<'_, S>, &'static str> {
+        if self.state.is_null() {
+            unsafe {
+                let conversation = conversation::find_or_create_conversation(self.pinfo);
+                let mut state = conversation::conversation_get_proto_data(conversation, self.proto_id)
+                    as *mut std::cell::RefCell<S>;
+                if state.is_null() {
+                    state = Box::into_raw(Box::new(std::cell::RefCell::new(S::default())));
+                    conversation::conversation_add_proto_data(conversation, self.proto_id, state as *mut libc::c_void);
+                    // The conversations are in file scope, so the state goes away when that memory is freed.
+                    drop_with_file_scope(state);
+                }
+                self.state = state;
+            }
+        }
+        unsafe {
+            return (*self.state)
+                .try_borrow_mut()
+                .map_err(|_| "The conversation state is already borrowed.");
+        }
+    }
+}
+
+/// A box to drop when the file scope memory is freed, with the function that drops it as its type.
+struct FileScopedBox {
+    data: usize,
+    drop: unsafe fn(usize),
+}
+
+/// The boxes to drop when the file scope memory is freed, a single wmem callback drops all of them instead of
+/// registering one for every box.
+static FILE_SCOPED: std::sync::Mutex<Vec<FileScopedBox>> = std::sync::Mutex::new(Vec::new());
+
+/// Drop the box when the file scope memory is freed, which happens when the capture file is closed.
+unsafe fn drop_with_file_scope<T>(data: *mut T) {
+    let mut boxes = FILE_SCOPED.lock().unwrap_or_else(|e| e.into_inner());
+    if boxes.is_empty() {
+        wmem_user_cb::wmem_register_callback(
+            wmem::wmem_file_scope(),
+            Some(drop_file_scoped_boxes),
+            std::ptr::null_mut(),
+        );
+    }
+    boxes.push(FileScopedBox {
+        data: data as usize,
+        drop: drop_box::<T>,
+    });
+}
+
+unsafe fn drop_box<T>(data: usize) {
+    drop(Box::from_raw(data as *mut T));
+}
+
+/// Callback to drop the boxes of [`drop_with_file_scope`] when the file scope memory is freed.
+unsafe extern "C" fn drop_file_scoped_boxes(
+    _allocator: *mut wmem::wmem_allocator_t,
+    _event: wmem_user_cb::wmem_cb_event_t,
+    _data: *mut libc::c_void,
+) -> i32 {
+    // Taken out first, the drops may use the file scope again.
+    let boxes = std::mem::take(&mut *FILE_SCOPED.lock().unwrap_or_else(|e| e.into_inner()));
+    for file_scoped in boxes {
+        (file_scoped.drop)(file_scoped.data);
+    }
+    return 0; // Unregister, the first box of the next file registers it again.
+}
+
+/// The closures passed to [`tcp_dissect_pdus`], wireshark hands this back to the trampolines as user data.
+struct PduDissection<'a> {
+    proto_id: i32,
+    get_pdu_len: &'a mut dyn FnMut(&mut PacketInfo, &mut TVB, usize) -> usize,
+    dissect_pdu: &'a mut dyn FnMut(&mut PacketInfo, &mut ProtoTree, &mut TVB) -> usize,
+}
+
+extern "C" fn pdu_len_trampoline(
+    pinfo: *mut packet_info::packet_info,
+    tvb: *mut tvbuff::tvbuff_t,
+    offset: i32,
+    data: *mut libc::c_void,
+) -> u32 {
+    unsafe {
+        let pdus = &mut *(data as *mut PduDissection);
+        let mut pinfo = PacketInfo::with_proto_id(pinfo, pdus.proto_id);
+        let mut tvb = TVB::from_ptr(tvb);
+        return (pdus.get_pdu_len)(&mut pinfo, &mut tvb, offset as usize) as u32;
+    }
+}
+
+extern "C" fn dissect_pdu_trampoline(
+    tvb: *mut tvbuff::tvbuff_t,
+    pinfo: *mut packet_info::packet_info,
+    tree: *mut proto::proto_tree,
+    data: *mut libc::c_void,
+) -> i32 {
+    unsafe {
+        let pdus = &mut *(data as *mut PduDissection);
+        let mut proto = ProtoTree::from_ptrs(tree, pinfo);
+        let mut pinfo = PacketInfo::with_proto_id(pinfo, pdus.proto_id);
+        let mut tvb = TVB::from_ptr(tvb);
+        return (pdus.dissect_pdu)(&mut pinfo, &mut proto, &mut tvb) as i32;
+    }
+}
+
+/// Dissect a protocol running over tcp, where PDUs can span segments and segments can hold multiple PDUs. Once
+/// `fixed_len` bytes of a PDU are available `get_pdu_len` is called with the offset the PDU starts at, it must return
+/// the length of the whole PDU, read from its header. Every complete PDU is then passed to `dissect_pdu` in its own
+/// TVB. If `desegment` is true PDUs that don't fit in the segment are reassembled by tcp, this is usually a preference.
+/// Returns the number of bytes consumed, the result of dissect can be returned as is.
+pub fn tcp_dissect_pdus<L, D>(
+    pinfo: &mut PacketInfo,
+    proto: &mut ProtoTree,
+    tvb: &mut TVB,
+    desegment: bool,
+    fixed_len: usize,
+    mut get_pdu_len: L,
+    mut dissect_pdu: D,
+) -> usize
+where
+    L: FnMut(&mut PacketInfo, &mut TVB, usize) -> usize,
+    D: FnMut(&mut PacketInfo, &mut ProtoTree, &mut TVB) -> usize,
+{
+    let mut pdus = PduDissection {
+        proto_id: pinfo.proto_id,
+        get_pdu_len: &mut get_pdu_len,
+        dissect_pdu: &mut dissect_pdu,
+    };
+    unsafe {
+        return packet_tcp::tcp_dissect_pdus(
+            tvb.into(),
+            pinfo.into(),
+            proto.tree,
+            desegment as i32,
+            fixed_len as u32,
+            Some(pdu_len_trampoline),
+            Some(dissect_pdu_trampoline),
+            &mut pdus as *mut PduDissection as *mut libc::c_void,
+        ) as usize;
+    }
+}
+
+/// A table to reassemble fragmented data in, registered through [`crate::dissector::Dissector::get_reassembly_tables`].
+/// The fragments are matched by their id and the addresses (and ports) of the packet.
+#[derive(Copy, Clone)]
+pub struct ReassemblyTable {
+    table: *mut reassemble::reassembly_table,
+    items: *const reassemble::fragment_items,
+    name: *const libc::c_char,
+}
+
+// The table and items are registered once and live for the remainder of the program, wireshark guards the table.
+unsafe impl Send for ReassemblyTable {}
+unsafe impl Sync for ReassemblyTable {}
+
+impl ReassemblyTable {
+    /// Create this structure from the registered table, the fragment items and the name of the reassembled data.
+    ///
+    /// # Safety
+    ///
+    /// The table must have been registered with `reassembly_table_register`, the items must hold registered field and
+    /// subtree indices and the name must be nul terminated. Wireshark keeps using all three while it reassembles, in
+    /// later packets, so they must never be freed.
+    pub unsafe fn from_ptr(
+        table: *mut reassemble::reassembly_table,
+        items: *const reassemble::fragment_items,
+        name: *const libc::c_char,
+    ) -> ReassemblyTable {
+        return ReassemblyTable { table, items, name };
+    }
+
+    /// Add the `length` bytes at `offset` in the tvb as the fragment that goes at `fragment_offset` bytes in the data
+    /// with this id, `more_fragments` is false for the last fragment. The fragments are shown in the tree, if the data
+    /// is complete in this frame the reassembled data is returned as a new data source, otherwise this returns None.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_fragment(
+        self: &Self,
+        pinfo: &mut PacketInfo,
+        proto: &mut ProtoTree,
+        tvb: &mut TVB,
+        offset: usize,
+        id: u32,
+        fragment_offset: u32,
+        length: u32,
+        more_fragments: bool,
+    ) -> Option<TVB> {
+        unsafe {
+            let head = reassemble::fragment_add_check(
+                self.table,
+                tvb.into(),
+                offset as i32,
+                pinfo.pinfo,
+                id,
+                std::ptr::null(),
+                fragment_offset,
+                length,
+                more_fragments as i32,
+            );
+            return self.process(pinfo, proto, tvb, offset, head);
+        }
+    }
+
+    /// Like [`ReassemblyTable::add_fragment`], but with the sequence number of the fragment instead of its offset.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_fragment_seq(
+        self: &Self,
+        pinfo: &mut PacketInfo,
+        proto: &mut ProtoTree,
+        tvb: &mut TVB,
+        offset: usize,
+        id: u32,
+        fragment_number: u32,
+        length: u32,
+        more_fragments: bool,
+    ) -> Option<TVB> {
+        unsafe {
+            let head = reassemble::fragment_add_seq_check(
+                self.table,
+                tvb.into(),
+                offset as i32,
+                pinfo.pinfo,
+                id,
+                std::ptr::null(),
+                fragment_number,
+                length,
+                more_fragments as i32,
+            );
+            return self.process(pinfo, proto, tvb, offset, head);
+        }
+    }
+
+    unsafe fn process(
+        self: &Self,
+        pinfo: &mut PacketInfo,
+        proto: &mut ProtoTree,
+        tvb: &mut TVB,
+        offset: usize,
+        head: *mut reassemble::fragment_head,
+    ) -> Option<TVB> {
+        let reassembled = reassemble::process_reassembled_data(
+            tvb.into(),
+            offset as i32,
+            pinfo.into(),
+            self.name,
+            head,
+            self.items,
+            std::ptr::null_mut(),
+            proto.tree,
+        );
+        if reassembled.is_null() {
+            return None;
+        }
+        return Some(TVB::from_ptr(reassembled));
+    }
+}
+
+/// Puts the bytes of a value from the tvb in big endian order, left aligned in B, bytes that don't fit are dropped.
+#[cfg(any(test, not(ws_ge_4_0)))]
+fn big_endian_bytes<B: Default + AsMut<[u8]>>(mut data: Vec<u8>, little_endian: bool) -> B {
+    let mut bytes = B::default();
+    let size = bytes.as_mut().len().min(data.len());
+    data.truncate(size);
+    if little_endian {
+        data.reverse();
+    }
+    bytes.as_mut()[..size].copy_from_slice(&data);
+    return bytes;
+}
+
+/// Sign extends a signed integer of length bytes, left aligned in big endian order.
+#[cfg(any(test, not(ws_ge_3_0)))]
+fn sign_extend(bytes: [u8; 8], length: usize) -> i64 {
+    // The bytes are at the start of the array, move them up and let the shift sign extend them.
+    let unused = 8 - length.clamp(1, 8);
+    return i64::from_be_bytes(bytes) >> (8 * unused);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_type_accepts_the_listed_types() {
+        assert!(check_type(FieldType::UINT32, UINT_TYPES).is_ok());
+        assert!(check_type(FieldType::FRAMENUM, UINT_TYPES).is_ok());
+        assert!(check_type(FieldType::INT24, INT_TYPES).is_ok());
+        assert!(check_type(FieldType::UINT40, UINT64_TYPES).is_ok());
+        assert!(check_type(FieldType::INT64, INT64_TYPES).is_ok());
+        assert!(check_type(FieldType::STRINGZ, STRING_TYPES).is_ok());
+        assert!(check_type(FieldType::UINT_BYTES, BYTES_TYPES).is_ok());
+    }
+
+    #[test]
+    fn check_type_rejects_other_types() {
+        // Wireshark has separate functions for the values of up to 32 and up to 64 bits.
+        assert!(check_type(FieldType::UINT64, UINT_TYPES).is_err());
+        assert!(check_type(FieldType::UINT32, UINT64_TYPES).is_err());
+        assert!(check_type(FieldType::INT32, UINT_TYPES).is_err());
+        assert!(check_type(FieldType::UINT32, INT_TYPES).is_err());
+        assert!(check_type(FieldType::INT32, INT64_TYPES).is_err());
+        assert!(check_type(FieldType::BYTES, STRING_TYPES).is_err());
+        assert!(check_type(FieldType::STRING, BYTES_TYPES).is_err());
+    }
+
+    #[test]
+    fn field_type_lists_are_disjoint() {
+        let lists = [
+            UINT_TYPES,
+            INT_TYPES,
+            UINT64_TYPES,
+            INT64_TYPES,
+            STRING_TYPES,
+            BYTES_TYPES,
+        ];
+        for (i, a) in lists.iter().enumerate() {
+            for b in lists.iter().skip(i + 1) {
+                assert!(a.iter().all(|t| !b.contains(t)), "{:?} and {:?} overlap", a, b);
+            }
+        }
+    }
+
+    fn ipv4(address: [u8; 4]) -> Address {
+        return Address {
+            address_type: AddressType::IPv4,
+            data: address.to_vec(),
+        };
+    }
+
+    #[test]
+    fn direction_differs_between_the_two_sides() {
+        let (a, b) = (ipv4([10, 0, 0, 1]), ipv4([10, 0, 0, 2]));
+        assert_eq!(Direction::between(a.clone(), 5000, b.clone(), 80), Direction::Forward);
+        assert_eq!(Direction::between(b, 80, a, 5000), Direction::Backward);
+    }
+
+    #[test]
+    fn direction_uses_the_ports_for_equal_addresses() {
+        let a = ipv4([127, 0, 0, 1]);
+        assert_eq!(Direction::between(a.clone(), 80, a.clone(), 5000), Direction::Forward);
+        assert_eq!(Direction::between(a.clone(), 5000, a.clone(), 80), Direction::Backward);
+        assert_eq!(Direction::between(a.clone(), 80, a, 80), Direction::Forward);
+    }
+
+    #[test]
+    fn direction_compares_the_address_type_first() {
+        let ether = Address {
+            address_type: AddressType::ETHER,
+            data: vec![0xff; 6],
+        };
+        // AT_ETHER is below AT_IPv4, whatever the bytes of the addresses are.
+        assert_eq!(
+            Direction::between(ether.clone(), 0, ipv4([0, 0, 0, 0]), 0),
+            Direction::Forward
+        );
+        assert_eq!(Direction::between(ipv4([0, 0, 0, 0]), 0, ether, 0), Direction::Backward);
+    }
+
+    #[test]
+    fn big_endian_bytes_keeps_big_endian() {
+        let bytes: [u8; 4] = big_endian_bytes(vec![1, 2, 3, 4], false);
+        assert_eq!(bytes, [1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn big_endian_bytes_reverses_little_endian() {
+        let bytes: [u8; 4] = big_endian_bytes(vec![4, 3, 2, 1], true);
+        assert_eq!(bytes, [1, 2, 3, 4]);
+        assert_eq!(
+            f32::from_be_bytes(big_endian_bytes(1.5f32.to_le_bytes().to_vec(), true)),
+            1.5
+        );
+    }
+
+    #[test]
+    fn big_endian_bytes_left_aligns_short_values() {
+        let bytes: [u8; 8] = big_endian_bytes(vec![1, 2, 3], false);
+        assert_eq!(bytes, [1, 2, 3, 0, 0, 0, 0, 0]);
+        let bytes: [u8; 8] = big_endian_bytes(vec![3, 2, 1], true);
+        assert_eq!(bytes, [1, 2, 3, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn big_endian_bytes_drops_what_does_not_fit() {
+        let bytes: [u8; 2] = big_endian_bytes(vec![1, 2, 3, 4], false);
+        assert_eq!(bytes, [1, 2]);
+    }
+
+    #[test]
+    fn sign_extend_short_values() {
+        assert_eq!(sign_extend([0xff, 0xfe, 0, 0, 0, 0, 0, 0], 2), -2);
+        assert_eq!(sign_extend([0x7f, 0xff, 0, 0, 0, 0, 0, 0], 2), 0x7fff);
+        assert_eq!(sign_extend([0x80, 0, 0, 0, 0, 0, 0, 0], 1), -128);
+        assert_eq!(sign_extend([0xff, 0xff, 0xff, 0xfb, 0, 0, 0, 0], 4), -5);
+    }
+
+    #[test]
+    fn sign_extend_full_width() {
+        let bytes = (-1234567890123i64).to_be_bytes();
+        assert_eq!(sign_extend(bytes, 8), -1234567890123);
+        // Lengths outside of 1 to 8 bytes are clamped, like the length of the item.
+        assert_eq!(sign_extend(bytes, 12), -1234567890123);
+        assert_eq!(sign_extend([0xff, 0, 0, 0, 0, 0, 0, 0], 0), -1);
+    }
+
+    #[test]
+    fn column_list_follows_the_declaration_order() {
+        for (index, column) in Column::ALL.iter().enumerate() {
+            assert_eq!(*column as usize, index, "{:?} is out of order", column);
+        }
+    }
+
+    #[test]
+    fn column_formats_are_nul_terminated_and_unique() {
+        for (index, column) in Column::ALL.iter().enumerate() {
+            let format = column.format();
+            assert!(format.ends_with('\0') && !format[..format.len() - 1].contains('\0'));
+            assert!(Column::ALL[index + 1..].iter().all(|other| other.format() != format));
+        }
     }
 }
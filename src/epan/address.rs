@@ -0,0 +1,18 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+#![allow(non_upper_case_globals)]
+
+// The first few address types, these haven't changed between releases.
+pub const AT_NONE: i32 = 0;
+pub const AT_ETHER: i32 = 1;
+pub const AT_IPv4: i32 = 2;
+pub const AT_IPv6: i32 = 3;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct address {
+    pub type_: i32,
+    pub len: i32,
+    pub data: *const libc::c_void,
+    pub priv_: *mut libc::c_void,
+}
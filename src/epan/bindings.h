@@ -0,0 +1,11 @@
+/* Headers the bindgen feature generates the epan bindings from, see build.rs. */
+
+/* Before 3.0 the installed headers expect config.h to be included first. */
+#if __has_include(<config.h>)
+#include <config.h>
+#endif
+
+#include <glib.h>
+#include <epan/packet.h>
+#include <epan/proto.h>
+#include <epan/ftypes/ftypes.h>
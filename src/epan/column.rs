@@ -0,0 +1,10 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Index of the column with this format string, like "%p" for the protocol, -1 if there is none.
+    pub fn get_column_format_from_str(str_: *const libc::c_char) -> i32;
+}
+}
@@ -0,0 +1,31 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+// The column info lives in the packet info, the functions below do nothing if it is null, which happens when the
+// packet list isn't being built. The column is an index as returned by get_column_format_from_str, the enum that
+// holds the indices differs between releases.
+#[repr(C)]
+pub struct column_info {
+    _private: [u8; 0],
+}
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Clear the column, unless it is fenced.
+    pub fn col_clear(cinfo: *mut column_info, col: i32);
+
+    // Set the column to this string, the string is not copied so it must outlive the packet list.
+    pub fn col_set_str(cinfo: *mut column_info, col: i32, str_: *const libc::c_char);
+
+    // Set and append to the column, these copy the string.
+    pub fn col_add_str(cinfo: *mut column_info, col: i32, str_: *const libc::c_char);
+    pub fn col_append_str(cinfo: *mut column_info, col: i32, str_: *const libc::c_char);
+
+    // Append the separator and the string, the separator is omitted if the column is empty.
+    pub fn col_append_sep_str(cinfo: *mut column_info, col: i32, sep: *const libc::c_char, str_: *const libc::c_char);
+
+    // Everything in the column up to here is kept by set, add and clear, they only change what comes after the fence.
+    pub fn col_set_fence(cinfo: *mut column_info, col: i32);
+}
+}
@@ -0,0 +1,21 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use super::packet_info::packet_info;
+
+#[repr(C)]
+pub struct conversation_t {
+    _private: [u8; 0],
+}
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Find the conversation of the addresses and ports of the packet, creating it if it doesn't exist yet.
+    pub fn find_or_create_conversation(pinfo: *mut packet_info) -> *mut conversation_t;
+
+    // Data a protocol stores in a conversation, it lives as long as the conversation, so in file scope.
+    pub fn conversation_add_proto_data(conv: *mut conversation_t, proto: i32, proto_data: *mut libc::c_void);
+    pub fn conversation_get_proto_data(conv: *const conversation_t, proto: i32) -> *mut libc::c_void;
+}
+}
@@ -0,0 +1,137 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+//! Resolving the wireshark functions at runtime.
+//!
+//! Normally the plugin links against libwireshark and glib, which ties it to the soname it was built against. With the
+//! `dynamic` feature the functions are instead looked up with `dlsym` in the process the plugin is loaded into, so the
+//! same plugin works with any compatible libwireshark. Before registering anything [`missing_symbols`] is checked,
+//! if wireshark lacks any of the functions the dissectors are not registered.
+
+use std::sync::atomic::{AtomicPtr, Ordering};
+
+/// A function in the host process, looked up the first time it is needed.
+pub struct Symbol {
+    /// Nul terminated name of the symbol.
+    name: &'static str,
+    address: AtomicPtr<libc::c_void>,
+}
+
+impl Symbol {
+    pub const fn new(name: &'static str) -> Symbol {
+        Symbol {
+            name,
+            address: AtomicPtr::new(std::ptr::null_mut()),
+        }
+    }
+
+    /// The name of the symbol, without the nul terminator.
+    pub fn name(self: &Self) -> &'static str {
+        return self.name.trim_end_matches('\0');
+    }
+
+    /// Look up the symbol, returns None if the host process doesn't have it.
+    pub fn resolve(self: &Self) -> Option<*mut libc::c_void> {
+        let address = self.address.load(Ordering::Relaxed);
+        if !address.is_null() {
+            return Some(address);
+        }
+        let address = unsafe { libc::dlsym(libc::RTLD_DEFAULT, self.name.as_ptr() as *const libc::c_char) };
+        if address.is_null() {
+            return None;
+        }
+        self.address.store(address, Ordering::Relaxed);
+        return Some(address);
+    }
+
+    /// The address of the symbol, panics if it doesn't exist, registration makes sure this doesn't happen.
+    pub fn get(self: &Self) -> *mut libc::c_void {
+        match self.resolve() {
+            Some(address) => address,
+            None => panic!("Symbol {} not found in the wireshark process.", self.name()),
+        }
+    }
+}
+
+/// Declares functions from a C library. By default these end up in an extern block linking the library, with the
+/// `dynamic` feature each becomes a function that calls the symbol resolved at runtime. The symbols are also collected
+/// by their nul terminated names in a `SYMBOLS` slice in the invoking module, such that [`missing_symbols`] can check them.
+macro_rules! dynamic_extern {
+    (
+        #[link(name = $lib:literal)]
+        extern "C" {
+            $(
+                $(#[$meta:meta])*
+                pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;
+            )*
+        }
+    ) => {
+        #[cfg(not(feature = "dynamic"))]
+        #[link(name = $lib)]
+        extern "C" {
+            $(
+                $(#[$meta])*
+                pub fn $name($($arg: $ty),*) $(-> $ret)?;
+            )*
+        }
+
+        $(
+            #[cfg(feature = "dynamic")]
+            $(#[$meta])*
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                static SYMBOL: $crate::epan::dynamic::Symbol =
+                    $crate::epan::dynamic::Symbol::new(concat!(stringify!($name), "\0"));
+                let function: unsafe extern "C" fn($($ty),*) $(-> $ret)? = std::mem::transmute(SYMBOL.get());
+                return function($($arg),*);
+            }
+        )*
+
+        #[cfg(feature = "dynamic")]
+        #[allow(unused_doc_comments)] // The doc comments of the functions end up on the names.
+        pub(crate) const SYMBOLS: &[&str] = &[
+            $(
+                $(#[$meta])*
+                concat!(stringify!($name), "\0"),
+            )*
+        ];
+    };
+}
+
+/// Names of the functions the host process doesn't provide, this is always empty if wireshark is linked.
+pub fn missing_symbols() -> Vec<&'static str> {
+    #[cfg(feature = "dynamic")]
+    {
+        let symbols = [
+            super::column::SYMBOLS,
+            super::column_utils::SYMBOLS,
+            super::conversation::SYMBOLS,
+            super::epan::SYMBOLS,
+            super::expert::SYMBOLS,
+            super::expert::VARIADIC_SYMBOLS,
+            super::ftypes::SYMBOLS,
+            super::glib::SYMBOLS,
+            super::glib::VARIADIC_SYMBOLS,
+            super::packet::SYMBOLS,
+            super::packet_tcp::SYMBOLS,
+            super::prefs::SYMBOLS,
+            super::proto::SYMBOLS,
+            super::proto::VARIADIC_SYMBOLS,
+            super::proto_data::SYMBOLS,
+            super::range::SYMBOLS,
+            super::reassemble::SYMBOLS,
+            super::reassemble::DATA_SYMBOLS,
+            super::tvbuff::SYMBOLS,
+            super::wmem::SYMBOLS,
+            super::wmem_user_cb::SYMBOLS,
+        ];
+        return symbols
+            .iter()
+            .flat_map(|s| s.iter())
+            .map(|name| Symbol::new(name))
+            .filter(|s| s.resolve().is_none())
+            .map(|s| s.name())
+            .collect();
+    }
+    #[cfg(not(feature = "dynamic"))]
+    return vec![];
+}
@@ -0,0 +1,10 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    /// Returns the version of the running epan, like "3.6.2".
+    pub fn epan_get_version() -> *const libc::c_char;
+}
+}
@@ -0,0 +1,173 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use super::packet_info::packet_info;
+use super::proto::hf_register_info;
+use super::proto::proto_item;
+use super::proto::proto_tree;
+use super::tvbuff::tvbuff_t;
+
+/// The group of an expert info, what kind of problem it is about.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpertGroup {
+    CHECKSUM = 0x01000000,
+    SEQUENCE = 0x02000000,
+    RESPONSE_CODE = 0x03000000,
+    REQUEST_CODE = 0x04000000,
+    UNDECODED = 0x05000000,
+    REASSEMBLE = 0x06000000,
+    MALFORMED = 0x07000000,
+    DEBUG = 0x08000000,
+    PROTOCOL = 0x09000000,
+    SECURITY = 0x0a000000,
+    COMMENTS_GROUP = 0x0b000000,
+    DECRYPTION = 0x0c000000,
+    ASSUMPTION = 0x0d000000,
+    DEPRECATED = 0x0e000000,
+}
+
+/// The severity of an expert info.
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExpertSeverity {
+    COMMENT = 0x00100000,
+    CHAT = 0x00200000,
+    NOTE = 0x00400000,
+    WARN = 0x00600000,
+    ERROR = 0x00800000,
+}
+
+#[repr(C)]
+pub struct expert_module_t {
+    _private: [u8; 0],
+}
+
+/// Index of a registered expert info, wireshark fills this in during registration.
+#[repr(C)]
+#[derive(Clone, Copy, Debug)]
+pub struct expert_field {
+    pub ei: i32,
+    pub hf: i32,
+}
+impl Default for expert_field {
+    fn default() -> Self {
+        expert_field { ei: -1, hf: -1 }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct expert_field_info {
+    pub name: *const libc::c_char,
+    pub group: i32,
+    pub severity: i32,
+    pub summary: *const libc::c_char,
+    // Below here is filled in by wireshark, the equivalent of EXPFILL.
+    pub id: i32,
+    pub protocol: *const libc::c_char,
+    pub orig_severity: i32,
+    pub hf_info: hf_register_info,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct ei_register_info {
+    pub ids: *mut expert_field,
+    pub eiinfo: expert_field_info,
+}
+
+/// Expert info index, this should NEVER be instantiated by the user, they are provided after registration.
+#[derive(Clone, Copy, Debug)]
+#[repr(transparent)]
+pub struct ExpertIndex(pub expert_field);
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    pub fn expert_register_protocol(id: i32) -> *mut expert_module_t;
+    pub fn expert_register_field_array(module: *mut expert_module_t, ei: *mut ei_register_info, num_records: i32);
+
+    pub fn expert_add_info(pinfo: *mut packet_info, pi: *mut proto_item, eiindex: *const expert_field)
+        -> *mut proto_item;
+    pub fn proto_tree_add_expert(
+        tree: *mut proto_tree,
+        pinfo: *mut packet_info,
+        eiindex: *const expert_field,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+    ) -> *mut proto_item;
+}
+}
+
+// Variadic functions can't go through dynamic_extern, with the dynamic feature these take a single argument for the
+// format, which is all we pass anyway.
+#[cfg(not(feature = "dynamic"))]
+#[link(name = "wireshark")]
+extern "C" {
+    pub fn expert_add_info_format(
+        pinfo: *mut packet_info,
+        pi: *mut proto_item,
+        eiindex: *const expert_field,
+        format: *const libc::c_char,
+        ...
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_expert_format(
+        tree: *mut proto_tree,
+        pinfo: *mut packet_info,
+        eiindex: *const expert_field,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        format: *const libc::c_char,
+        ...
+    ) -> *mut proto_item;
+}
+
+#[cfg(feature = "dynamic")]
+pub unsafe fn expert_add_info_format(
+    pinfo: *mut packet_info,
+    pi: *mut proto_item,
+    eiindex: *const expert_field,
+    format: *const libc::c_char,
+    argument: *const libc::c_char,
+) -> *mut proto_item {
+    static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new("expert_add_info_format\0");
+    let function: unsafe extern "C" fn(
+        *mut packet_info,
+        *mut proto_item,
+        *const expert_field,
+        *const libc::c_char,
+        ...
+    ) -> *mut proto_item = std::mem::transmute(SYMBOL.get());
+    return function(pinfo, pi, eiindex, format, argument);
+}
+
+#[cfg(feature = "dynamic")]
+pub unsafe fn proto_tree_add_expert_format(
+    tree: *mut proto_tree,
+    pinfo: *mut packet_info,
+    eiindex: *const expert_field,
+    tvb: *mut tvbuff_t,
+    start: i32,
+    length: i32,
+    format: *const libc::c_char,
+    argument: *const libc::c_char,
+) -> *mut proto_item {
+    static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new("proto_tree_add_expert_format\0");
+    let function: unsafe extern "C" fn(
+        *mut proto_tree,
+        *mut packet_info,
+        *const expert_field,
+        *mut tvbuff_t,
+        i32,
+        i32,
+        *const libc::c_char,
+        ...
+    ) -> *mut proto_item = std::mem::transmute(SYMBOL.get());
+    return function(tree, pinfo, eiindex, tvb, start, length, format, argument);
+}
+
+#[cfg(feature = "dynamic")]
+pub(crate) const VARIADIC_SYMBOLS: &[&str] = &["expert_add_info_format\0", "proto_tree_add_expert_format\0"];
@@ -0,0 +1,53 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+// Only the start of frame_data is here, up to and including the word holding the flags, it is never instantiated from
+// Rust. The flags are bitfields, which we can't express, so they're read from the word holding them.
+
+#[cfg(not(ws_ge_3_0))]
+#[repr(C)]
+#[derive(Debug)]
+pub struct frame_data {
+    pub pfd: *mut libc::c_void,
+    pub num: u32,
+    pub pkt_len: u32,
+    pub cap_len: u32,
+    pub cum_bytes: u32,
+    pub file_off: i64,
+    pub subnum: u16,
+    pub tsprec: i16,
+    /// passed_dfilter, dependent_of_displayed, encoding, visited, ... from the lowest bit up.
+    pub flags: u32,
+}
+
+/// Bit of the visited flag in frame_data.flags.
+#[cfg(not(ws_ge_3_0))]
+pub const FRAME_DATA_VISITED_BIT: u32 = 3;
+
+#[cfg(ws_ge_3_0)]
+#[repr(C)]
+#[derive(Debug)]
+pub struct frame_data {
+    pub num: u32,
+    pub pkt_len: u32,
+    pub cap_len: u32,
+    pub cum_bytes: u32,
+    pub file_off: i64,
+    pub pfd: *mut libc::c_void,
+    pub dependent_frames: *mut libc::c_void,
+    pub color_filter: *const libc::c_void,
+    /// The guint8 tsprec shares this word with the bitfields that follow it, passed_dfilter, dependent_of_displayed,
+    /// encoding, visited, ... from bit 8 up.
+    pub flags: u32,
+}
+
+/// Bit of the visited flag in frame_data.flags.
+#[cfg(ws_ge_3_0)]
+pub const FRAME_DATA_VISITED_BIT: u32 = 11;
+
+impl frame_data {
+    /// Whether the packet was dissected before, wireshark dissects all packets once when it loads a file.
+    pub fn visited(self: &Self) -> bool {
+        return (self.flags >> FRAME_DATA_VISITED_BIT) & 1 != 0;
+    }
+}
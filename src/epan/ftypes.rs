@@ -1,11 +1,19 @@
 // Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
 // SPDX-License-Identifier: GPL-2.0-or-later
 
+use super::FieldType;
 use core::fmt::Debug;
 
+// With the bindgen feature the types are generated from the headers instead of using the ones written below.
+#[cfg(feature = "bindgen")]
+pub use super::bindings::{ftenum, fvalue_t};
+
+/// The field types as wireshark knows them, the discriminants depend on the wireshark release, use
+/// [`super::FieldType`] for a type that's the same across releases.
+#[cfg(not(feature = "bindgen"))]
 #[repr(C)]
 #[allow(dead_code)]
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum ftenum {
     NONE, /* used for text labels with no value */
     PROTOCOL,
@@ -43,6 +51,8 @@ pub enum ftenum {
     IPv6,
     IPXNET,
     FRAMENUM, /* a UINT32, but if selected lets you go to frame with that number */
+    #[cfg(not(ws_ge_4_0))]
+    PCRE, /* a compiled Perl-Compatible Regular Expression object, removed in 4.0 */
     GUID,     /* GUID, UUID */
     OID,      /* OBJECT IDENTIFIER */
     EUI64,
@@ -52,8 +62,9 @@ pub enum ftenum {
     SYSTEM_ID,
     STRINGZPAD, /* null-padded string */
     FCWWN,
-    STRINGZTRUNC, /* null-truncated string */
-    NUM_TYPES,    /* last item number plus one */
+    #[cfg(ws_ge_3_4)]
+    STRINGZTRUNC, /* null-truncated string, added in 3.4 */
+    NUM_TYPES, /* last item number plus one */
 }
 
 impl Default for ftenum {
@@ -64,11 +75,95 @@ impl Default for ftenum {
 
 unsafe impl Send for ftenum {}
 
+// Helper to write the conversions between the stable and raw types without listing every variant twice.
+macro_rules! field_type_conversions {
+    ($($variant:ident),*) => {
+        impl From<FieldType> for ftenum {
+            fn from(v: FieldType) -> Self {
+                match v {
+                    $(FieldType::$variant => ftenum::$variant,)*
+                    #[cfg(ws_ge_3_4)]
+                    FieldType::STRINGZTRUNC => ftenum::STRINGZTRUNC,
+                    // Not available in this release, a null terminated string is the closest.
+                    #[cfg(not(ws_ge_3_4))]
+                    FieldType::STRINGZTRUNC => ftenum::STRINGZ,
+                }
+            }
+        }
+
+        impl From<ftenum> for FieldType {
+            fn from(v: ftenum) -> Self {
+                match v {
+                    $(ftenum::$variant => FieldType::$variant,)*
+                    #[cfg(ws_ge_3_4)]
+                    ftenum::STRINGZTRUNC => FieldType::STRINGZTRUNC,
+                    // These never describe a field.
+                    #[cfg(not(ws_ge_4_0))]
+                    ftenum::PCRE => FieldType::NONE,
+                    ftenum::NUM_TYPES => FieldType::NONE,
+                    // The generated enum may hold more than we know about.
+                    #[allow(unreachable_patterns)]
+                    _ => FieldType::NONE,
+                }
+            }
+        }
+    };
+}
+
+field_type_conversions!(
+    NONE,
+    PROTOCOL,
+    BOOLEAN,
+    CHAR,
+    UINT8,
+    UINT16,
+    UINT24,
+    UINT32,
+    UINT40,
+    UINT48,
+    UINT56,
+    UINT64,
+    INT8,
+    INT16,
+    INT24,
+    INT32,
+    INT40,
+    INT48,
+    INT56,
+    INT64,
+    IEEE_11073_SFLOAT,
+    IEEE_11073_FLOAT,
+    FLOAT,
+    DOUBLE,
+    ABSOLUTE_TIME,
+    RELATIVE_TIME,
+    STRING,
+    STRINGZ,
+    UINT_STRING,
+    ETHER,
+    BYTES,
+    UINT_BYTES,
+    IPv4,
+    IPv6,
+    IPXNET,
+    FRAMENUM,
+    GUID,
+    OID,
+    EUI64,
+    AX25,
+    VINES,
+    REL_OID,
+    SYSTEM_ID,
+    STRINGZPAD,
+    FCWWN
+);
+
 #[repr(C)]
 pub struct ftype_t {
     _private: [u8; 0],
 }
 
+#[cfg(all(not(ws_ge_4_0), not(feature = "bindgen")))]
 #[repr(C)]
 pub union fvalue_t_value_union {
     /* Put a few basic types in here */
@@ -93,12 +188,14 @@ pub union fvalue_t_value_union {
     _size: [u8; 24], // determined with sizeof from C.
 }
 
+#[cfg(all(not(ws_ge_4_0), not(feature = "bindgen")))]
 impl Debug for fvalue_t_value_union {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(f, "fvalue_t_value_union")
     }
 }
 
+#[cfg(all(not(ws_ge_4_0), not(feature = "bindgen")))]
 #[derive(Debug)]
 #[repr(C)]
 pub struct fvalue_t {
@@ -107,9 +204,18 @@ pub struct fvalue_t {
 
     /* The following is provided for private use
      * by the fvalue. */
-    fvalue_gboolean1: bool,
+    fvalue_gboolean1: i32, // gboolean
 }
 
+/// Opaque fvalue_t struct, it became private in 4.0, field_info only holds a pointer to it from then on.
+#[cfg(all(ws_ge_4_0, not(feature = "bindgen")))]
+#[derive(Debug)]
+#[repr(C)]
+pub struct fvalue_t {
+    _private: [u8; 0],
+}
+
+dynamic_extern! {
 #[link(name = "wireshark")]
 extern "C" {
     pub fn fvalue_type_ftenum(fv: *const fvalue_t) -> ftenum;
@@ -120,8 +226,10 @@ extern "C" {
     pub fn fvalue_get_sinteger64(fv: *const fvalue_t) -> i64;
     pub fn fvalue_get_floating(fv: *const fvalue_t) -> f64;
 
+    #[cfg(not(ws_ge_4_0))]
     pub fn fvalue_get(fv: *const fvalue_t) -> *const libc::c_void;
 
     //~ pub fn fvalue_type_name(fv: *const fvalue_t) -> *const libc::c_char;  // Doesn't exist after linking...?
     //~ pub fn fvalue_length(fv: *const fvalue_t) -> u32;  // likewise, doesn't exist after linking? odd
 }
+}
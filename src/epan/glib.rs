@@ -1,13 +1,18 @@
 // Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
 // SPDX-License-Identifier: GPL-2.0-or-later
 
+// With the bindgen feature this is generated from the headers instead.
+#[cfg(feature = "bindgen")]
+pub use super::bindings::GPtrArray;
+
+#[cfg(not(feature = "bindgen"))]
 #[derive(Debug)]
 #[repr(C)]
 pub struct GPtrArray {
     // actually defined as a single pointer, but index macro shows its a
     // list of void pointers behind a pointer.
-    pdata: *mut *mut libc::c_void,
-    len: u32,
+    pub pdata: *mut *mut libc::c_void,
+    pub len: u32,
 }
 impl GPtrArray {
     pub fn len(self: &Self) -> usize {
@@ -20,7 +25,35 @@ impl GPtrArray {
     }
 }
 
+dynamic_extern! {
 #[link(name = "glib-2.0")]
 extern "C" {
     pub fn g_ptr_array_free(array: *mut GPtrArray, free_seg: bool);
 }
+}
+
+/// Messages of this level end up in wireshark's log, they are shown on the console if there is one.
+pub const G_LOG_LEVEL_WARNING: i32 = 1 << 4;
+
+// Variadic, with the dynamic feature this takes a single argument for the format, which is all we pass anyway.
+#[cfg(not(feature = "dynamic"))]
+#[link(name = "glib-2.0")]
+extern "C" {
+    pub fn g_log(log_domain: *const libc::c_char, log_level: i32, format: *const libc::c_char, ...);
+}
+
+#[cfg(feature = "dynamic")]
+pub unsafe fn g_log(
+    log_domain: *const libc::c_char,
+    log_level: i32,
+    format: *const libc::c_char,
+    argument: *const libc::c_char,
+) {
+    static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new("g_log\0");
+    let function: unsafe extern "C" fn(*const libc::c_char, i32, *const libc::c_char, ...) =
+        std::mem::transmute(SYMBOL.get());
+    function(log_domain, log_level, format, argument);
+}
+
+#[cfg(feature = "dynamic")]
+pub(crate) const VARIADIC_SYMBOLS: &[&str] = &["g_log\0"];
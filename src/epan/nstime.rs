@@ -0,0 +1,17 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+// This one lives in wsutil, but it's all over the epan structs.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct nstime_t {
+    pub secs: libc::time_t,
+    pub nsecs: i32,
+}
+
+impl nstime_t {
+    /// The time in seconds, as floating point.
+    pub fn as_secs_f64(self: &Self) -> f64 {
+        return self.secs as f64 + self.nsecs as f64 * 1e-9;
+    }
+}
@@ -1,6 +1,7 @@
 // Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
 // SPDX-License-Identifier: GPL-2.0-or-later
 
+use super::ftypes::ftenum;
 use super::packet_info::packet_info;
 use super::proto::proto_tree;
 use super::proto::protocol_t;
@@ -11,12 +12,28 @@ use super::tvbuff::tvbuff_t;
 pub struct dissector_handle {
     _private: [u8; 0],
 }
-type dissector_handle_t = *mut dissector_handle;
+pub type dissector_handle_t = *mut dissector_handle;
 
-type dissector_t = Option<extern "C" fn(*mut tvbuff_t, *mut packet_info, *mut proto_tree, *mut libc::c_void) -> i32>;
+#[repr(C)]
+pub struct dissector_table {
+    _private: [u8; 0],
+}
+pub type dissector_table_t = *mut dissector_table;
+
+#[repr(C)]
+pub struct heur_dissector_list {
+    _private: [u8; 0],
+}
+pub type heur_dissector_list_t = *mut heur_dissector_list;
+
+/// Param for register_dissector_table with string tables, matching of the keys is case sensitive.
+pub const STRING_CASE_SENSITIVE: i32 = 0;
 
-type heur_dissector_t =
-    Option<extern "C" fn(*mut tvbuff_t, *mut packet_info, *mut proto_tree, *mut libc::c_void) -> bool>;
+pub type dissector_fn = extern "C" fn(*mut tvbuff_t, *mut packet_info, *mut proto_tree, *mut libc::c_void) -> i32;
+pub type dissector_t = Option<dissector_fn>;
+
+pub type heur_dissector_fn = extern "C" fn(*mut tvbuff_t, *mut packet_info, *mut proto_tree, *mut libc::c_void) -> bool;
+pub type heur_dissector_t = Option<heur_dissector_fn>;
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug)]
@@ -36,15 +53,64 @@ pub struct heur_dtbl_entry_t {
     pub enabled: bool,
 }
 
+dynamic_extern! {
 #[link(name = "wireshark")]
 extern "C" {
     pub fn register_postdissector(handle: dissector_handle_t);
     pub fn dissector_add_uint(abbrev: *const libc::c_char, pattern: u32, handle: dissector_handle_t);
     pub fn dissector_add_uint_range(abbrev: *const libc::c_char, range: *const epan_range, handle: dissector_handle_t);
-    //~ pub fn dissector_add_string(name: *const libc::c_char, pattern: *const libc::c_char, handle: dissector_handle_t);
+    pub fn dissector_add_uint_range_with_preference(
+        abbrev: *const libc::c_char,
+        range_str: *const libc::c_char,
+        handle: dissector_handle_t,
+    );
+    pub fn dissector_add_string(name: *const libc::c_char, pattern: *const libc::c_char, handle: dissector_handle_t);
     pub fn dissector_add_for_decode_as(name: *const libc::c_char, handle: dissector_handle_t);
 
     pub fn create_dissector_handle(dissector: dissector_t, proto: i32) -> dissector_handle_t;
+    pub fn register_dissector(name: *const libc::c_char, dissector: dissector_t, proto: i32) -> dissector_handle_t;
+
+    // Calling other dissectors.
+    pub fn find_dissector(name: *const libc::c_char) -> dissector_handle_t;
+    pub fn call_dissector(
+        handle: dissector_handle_t,
+        tvb: *mut tvbuff_t,
+        pinfo: *mut packet_info,
+        tree: *mut proto_tree,
+    ) -> i32;
+    pub fn call_dissector_with_data(
+        handle: dissector_handle_t,
+        tvb: *mut tvbuff_t,
+        pinfo: *mut packet_info,
+        tree: *mut proto_tree,
+        data: *mut libc::c_void,
+    ) -> i32;
+
+    // Dissector tables, for integer tables param is the display base, for string tables it's the case sensitivity.
+    pub fn register_dissector_table(
+        name: *const libc::c_char,
+        ui_name: *const libc::c_char,
+        proto: i32,
+        type_: ftenum,
+        param: i32,
+    ) -> dissector_table_t;
+    pub fn find_dissector_table(name: *const libc::c_char) -> dissector_table_t;
+    pub fn dissector_try_uint(
+        sub_dissectors: dissector_table_t,
+        uint_val: u32,
+        tvb: *mut tvbuff_t,
+        pinfo: *mut packet_info,
+        tree: *mut proto_tree,
+    ) -> i32;
+    pub fn dissector_try_string(
+        sub_dissectors: dissector_table_t,
+        string: *const libc::c_char,
+        tvb: *mut tvbuff_t,
+        pinfo: *mut packet_info,
+        tree: *mut proto_tree,
+        data: *mut libc::c_void,
+    ) -> i32;
+    pub fn call_data_dissector(tvb: *mut tvbuff_t, pinfo: *mut packet_info, tree: *mut proto_tree) -> i32;
 
     pub fn heur_dissector_add(
         name: *const libc::c_char,
@@ -54,5 +120,15 @@ extern "C" {
         proto: i32,
         enable: heuristic_enable_e,
     );
+    pub fn register_heur_dissector_list(name: *const libc::c_char, proto: i32) -> heur_dissector_list_t;
+    pub fn dissector_try_heuristic(
+        sub_dissectors: heur_dissector_list_t,
+        tvb: *mut tvbuff_t,
+        pinfo: *mut packet_info,
+        tree: *mut proto_tree,
+        hdtbl_entry: *mut *mut heur_dtbl_entry_t,
+        data: *mut libc::c_void,
+    ) -> bool;
     pub fn find_heur_dissector_by_unique_short_name(short_name: *const libc::c_char) -> *mut heur_dtbl_entry_t;
 }
+}
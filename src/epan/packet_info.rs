@@ -1,8 +1,50 @@
 // Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
 // SPDX-License-Identifier: GPL-2.0-or-later
 
+use super::address::address;
+use super::column_utils::column_info;
+use super::frame_data::frame_data;
+use super::nstime::nstime_t;
+
+// packet_info is enormous, only the start of it is here, up to and including p2p_dir. It's never instantiated from
+// Rust, so the remainder doesn't matter. The gbooleans are i32, they're plain ints in the releases we support.
 #[repr(C)]
+#[derive(Debug)]
 pub struct packet_info {
-    _private: [u8; 0],
+    pub current_proto: *const libc::c_char,
+    pub cinfo: *mut column_info,
+    pub presence_flags: u32,
+    pub num: u32,
+    pub abs_ts: nstime_t,
+    pub rel_ts: nstime_t,
+    pub fd: *mut frame_data,
+    pub pseudo_header: *mut libc::c_void,
+    pub rec: *mut libc::c_void, // phdr in 2.6, both are pointers.
+    pub data_src: *mut libc::c_void,
+    pub dl_src: address,
+    pub dl_dst: address,
+    pub net_src: address,
+    pub net_dst: address,
+    pub src: address,
+    pub dst: address,
+    pub vlan_id: u32,
+    pub noreassembly_reason: *const libc::c_char,
+    pub fragmented: i32,
+    pub flags: u32, // in_error_pkt and in_gre_pkt bitfields.
+    pub ptype: i32,
+    pub srcport: u32,
+    pub destport: u32,
+    pub match_uint: u32,
+    pub match_string: *const libc::c_char,
+    pub use_endpoint: i32,                // use_conv_addr_port_endpoints from 4.0
+    pub conv_endpoint: *mut libc::c_void, // conv_addr_port_endpoints from 4.0
+    #[cfg(ws_ge_4_0)]
+    pub conv_elements: *mut libc::c_void,
+    pub can_desegment: u16,
+    pub saved_can_desegment: u16,
+    pub desegment_offset: i32,
+    pub desegment_len: u32,
+    pub want_pdu_tracking: u16,
+    pub bytes_until_next_pdu: u32,
+    pub p2p_dir: i32,
 }
-// Hmm, packet_info is enormous, but we have to reach into it for column info. Let skip that for now.
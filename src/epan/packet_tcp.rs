@@ -0,0 +1,30 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+// From epan/dissectors/packet-tcp.h.
+
+use super::packet::dissector_t;
+use super::packet_info::packet_info;
+use super::proto::proto_tree;
+use super::tvbuff::tvbuff_t;
+
+/// Returns the length of the PDU that starts at offset, given at least fixed_len bytes of it.
+pub type tcp_pdu_len_fn = extern "C" fn(*mut packet_info, *mut tvbuff_t, i32, *mut libc::c_void) -> u32;
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Splits the tcp payload into PDUs and calls dissect_pdu for each complete one, if proto_desegment is true and a
+    // PDU doesn't fit in the segment it asks tcp to reassemble it. Returns the number of bytes consumed.
+    pub fn tcp_dissect_pdus(
+        tvb: *mut tvbuff_t,
+        pinfo: *mut packet_info,
+        tree: *mut proto_tree,
+        proto_desegment: i32,
+        fixed_len: u32,
+        get_pdu_len: Option<tcp_pdu_len_fn>,
+        dissect_pdu: dissector_t,
+        dissector_data: *mut libc::c_void,
+    ) -> i32;
+}
+}
@@ -0,0 +1,82 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use super::range::epan_range;
+
+#[repr(C)]
+pub struct module_t {
+    _private: [u8; 0],
+}
+
+/// Entry of the value list of an enum preference, the list ends with an entry that has a null name.
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct enum_val_t {
+    pub name: *const libc::c_char,
+    pub description: *const libc::c_char,
+    pub value: i32,
+}
+
+pub type prefs_apply_fn = extern "C" fn();
+
+/// The type wireshark stores bool preferences in, a gboolean before 4.2 and a bool from 4.2 on.
+#[cfg(not(ws_ge_4_2))]
+pub type BoolPreference = i32;
+#[cfg(ws_ge_4_2)]
+pub type BoolPreference = bool;
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    pub fn prefs_register_protocol(id: i32, apply_cb: Option<prefs_apply_fn>) -> *mut module_t;
+
+    pub fn prefs_register_bool_preference(
+        module: *mut module_t,
+        name: *const libc::c_char,
+        title: *const libc::c_char,
+        description: *const libc::c_char,
+        var: *mut BoolPreference,
+    );
+    pub fn prefs_register_uint_preference(
+        module: *mut module_t,
+        name: *const libc::c_char,
+        title: *const libc::c_char,
+        description: *const libc::c_char,
+        base: u32,
+        var: *mut u32,
+    );
+    pub fn prefs_register_enum_preference(
+        module: *mut module_t,
+        name: *const libc::c_char,
+        title: *const libc::c_char,
+        description: *const libc::c_char,
+        var: *mut i32,
+        enumvals: *const enum_val_t,
+        radio_buttons: i32,
+    );
+    // Wireshark replaces the string in var with its own copy, and frees and replaces that when it changes.
+    pub fn prefs_register_string_preference(
+        module: *mut module_t,
+        name: *const libc::c_char,
+        title: *const libc::c_char,
+        description: *const libc::c_char,
+        var: *mut *const libc::c_char,
+    );
+    pub fn prefs_register_filename_preference(
+        module: *mut module_t,
+        name: *const libc::c_char,
+        title: *const libc::c_char,
+        description: *const libc::c_char,
+        var: *mut *const libc::c_char,
+        for_writing: i32,
+    );
+    pub fn prefs_register_range_preference(
+        module: *mut module_t,
+        name: *const libc::c_char,
+        title: *const libc::c_char,
+        description: *const libc::c_char,
+        var: *mut *mut epan_range,
+        max_value: u32,
+    );
+}
+}
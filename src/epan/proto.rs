@@ -7,7 +7,13 @@
 use super::ftypes::ftenum;
 use super::ftypes::fvalue_t;
 use super::glib::GPtrArray;
+use super::nstime::nstime_t;
 use super::tvbuff::tvbuff_t;
+use super::wmem::wmem_allocator_t;
+
+// With the bindgen feature the structs are generated from the headers instead of using the ones written below.
+#[cfg(feature = "bindgen")]
+pub use super::bindings::{field_info, header_field_info, hf_ref_type, hf_register_info, proto_plugin};
 
 #[repr(u32)]
 #[derive(Clone, Copy, Debug)]
@@ -18,6 +24,17 @@ pub enum Encoding {
     STR_HEX = 0x02000000,
     STRING = 0x03000000,
     STR_MASK = 0x0000FFFE,
+    UTF_8 = 0x00000002,
+}
+impl Encoding {
+    /// Strings in ASCII and fields that have no encoding share their value with big endian.
+    pub const ASCII: Encoding = Encoding::BIG_ENDIAN;
+    pub const NA: Encoding = Encoding::BIG_ENDIAN;
+
+    /// Whether multi byte values are little endian.
+    pub fn is_little_endian(self: &Self) -> bool {
+        return (*self as u32) & (Encoding::LITTLE_ENDIAN as u32) != 0;
+    }
 }
 
 #[repr(i32)]
@@ -59,9 +76,39 @@ pub enum FieldDisplay {
 }
 impl FieldDisplay {
     pub const BASE_FLOAT: FieldDisplay = FieldDisplay::BASE_NONE;
-    pub const STR_UNICODE: FieldDisplay = FieldDisplay::BASE_NONE;
+    pub const STR_ASCII: FieldDisplay = FieldDisplay::BASE_NONE;
+    /// Float types in exponential notation, shares its value 7 with the STR_UNICODE variant, only available from 4.0
+    /// onwards.
+    #[cfg(ws_ge_4_0)]
+    pub const BASE_EXP: FieldDisplay = FieldDisplay::STR_UNICODE;
+
+    /// Convert the display value found in a header_field_info, this strips the flags wireshark stores in the upper
+    /// bits and returns BASE_NONE for unknown values.
+    pub fn from_raw(display: i32) -> FieldDisplay {
+        match display & 0xFF {
+            1 => FieldDisplay::BASE_DEC,
+            2 => FieldDisplay::BASE_HEX,
+            3 => FieldDisplay::BASE_OCT,
+            4 => FieldDisplay::BASE_DEC_HEX,
+            5 => FieldDisplay::BASE_HEX_DEC,
+            6 => FieldDisplay::BASE_CUSTOM,
+            7 => FieldDisplay::STR_UNICODE,
+            8 => FieldDisplay::SEP_DOT,
+            9 => FieldDisplay::SEP_DASH,
+            10 => FieldDisplay::SEP_COLON,
+            11 => FieldDisplay::SEP_SPACE,
+            12 => FieldDisplay::BASE_NETMASK,
+            13 => FieldDisplay::BASE_PT_UDP,
+            14 => FieldDisplay::BASE_PT_TCP,
+            15 => FieldDisplay::BASE_PT_DCCP,
+            16 => FieldDisplay::BASE_PT_SCTP,
+            17 => FieldDisplay::BASE_OUI,
+            _ => FieldDisplay::BASE_NONE,
+        }
+    }
 }
 
+#[cfg(not(feature = "bindgen"))]
 #[repr(C)]
 #[allow(dead_code)]
 #[derive(Debug)]
@@ -70,11 +117,13 @@ pub enum hf_ref_type {
     INDIRECT,
     DIRECT,
 }
+#[cfg(not(feature = "bindgen"))]
 impl Default for hf_ref_type {
     fn default() -> Self {
         hf_ref_type::NONE
     }
 }
+#[cfg(not(feature = "bindgen"))]
 unsafe impl Send for hf_ref_type {}
 
 /// Opaque proto_tree struct
@@ -94,12 +143,13 @@ pub struct protocol_t {
     _private: [u8; 0],
 }
 
+#[cfg(not(feature = "bindgen"))]
 #[repr(C)]
 pub struct header_field_info {
     pub name: *const libc::c_char,
     pub abbrev: *const libc::c_char,
     pub type_: ftenum,
-    pub display: FieldDisplay,
+    pub display: i32, // FieldDisplay in the lower bits, flags in the upper ones, see FieldDisplay::from_raw.
     pub strings: *const libc::c_char, // actually void ptr
     pub bitmask: u64,
     pub blurb: *const libc::c_char,
@@ -111,13 +161,14 @@ pub struct header_field_info {
     pub same_name_pref_id: i32,
     pub same_name_next: *mut header_field_info,
 }
+#[cfg(not(feature = "bindgen"))]
 impl Default for header_field_info {
     fn default() -> Self {
         header_field_info {
             name: 0 as *const libc::c_char,
             abbrev: 0 as *const libc::c_char,
             type_: Default::default(),
-            display: FieldDisplay::BASE_NONE,
+            display: FieldDisplay::BASE_NONE as i32,
             strings: 0 as *const libc::c_char,
             bitmask: 0,
             blurb: 0 as *const libc::c_char,
@@ -131,7 +182,9 @@ impl Default for header_field_info {
 }
 
 // printing everything causes segfaults?? :/
+#[cfg(not(feature = "bindgen"))]
 use core::fmt::Debug;
+#[cfg(not(feature = "bindgen"))]
 impl Debug for header_field_info {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         use std::ffi::CStr;
@@ -164,20 +217,54 @@ impl Debug for header_field_info {
         write!(f, "}}")
     }
 }
-/*
-*/
+
+impl header_field_info {
+    /// Header field info for a field that is about to be registered, the equivalent of HFILL in C.
+    pub fn new(
+        name: *const libc::c_char,
+        abbrev: *const libc::c_char,
+        type_: ftenum,
+        display: FieldDisplay,
+    ) -> header_field_info {
+        #[cfg(not(feature = "bindgen"))]
+        return header_field_info {
+            name,
+            abbrev,
+            type_,
+            display: display as i32,
+            ..Default::default()
+        };
+        #[cfg(feature = "bindgen")]
+        return header_field_info {
+            name,
+            abbrev,
+            type_,
+            display: display as i32,
+            strings: std::ptr::null(),
+            bitmask: 0,
+            blurb: std::ptr::null(),
+            id: -1,
+            parent: 0,
+            ref_type: hf_ref_type::NONE,
+            same_name_prev_id: -1,
+            same_name_next: std::ptr::null_mut(),
+        };
+    }
+}
 
 #[derive(Clone, Copy, Debug)]
 #[repr(transparent)]
 /// HF index, this should NEVER be instantiated by the user, they are returned by proto_register_field_array.
 pub struct HFIndex(pub i32);
 
+#[cfg(not(feature = "bindgen"))]
 #[derive(Debug)]
 #[repr(C)]
 pub struct hf_register_info {
     pub p_id: *mut HFIndex,        // written to by register() function
     pub hfinfo: header_field_info, // < the field info to be registered
 }
+#[cfg(not(feature = "bindgen"))]
 impl Default for hf_register_info {
     fn default() -> Self {
         hf_register_info {
@@ -187,6 +274,16 @@ impl Default for hf_register_info {
     }
 }
 
+impl hf_register_info {
+    /// Entry for the field registration array, wireshark writes the field's index to p_id.
+    pub fn new(p_id: *mut HFIndex, hfinfo: header_field_info) -> hf_register_info {
+        hf_register_info {
+            p_id: p_id as *mut _,
+            hfinfo,
+        }
+    }
+}
+
 const ITEM_LABEL_LENGTH: usize = 240;
 #[derive(Debug)]
 #[repr(C)]
@@ -194,6 +291,7 @@ pub struct item_label_t {
     representation: [libc::c_char; ITEM_LABEL_LENGTH],
 }
 
+#[cfg(not(feature = "bindgen"))]
 #[derive(Debug)]
 #[repr(C)]
 pub struct field_info {
@@ -215,15 +313,35 @@ pub struct field_info {
     /**< string for GUI tree */
     pub ds_tvb: *mut tvbuff_t,
     /**< data source tvbuff */
+    #[cfg(not(ws_ge_4_0))]
     pub value: fvalue_t,
+    #[cfg(ws_ge_4_0)]
+    pub value: *mut fvalue_t,
+    #[cfg(ws_ge_4_0)]
+    pub total_layer_num: i32,
+    /**< Hierarchical layer number, for all protocols. */
+    #[cfg(ws_ge_4_0)]
+    pub proto_layer_num: i32,
+    /*< Protocol layer number, for this protocol. */
+}
+impl field_info {
+    /// Pointer to the value, it's held inline before 4.0 and behind a pointer from 4.0 onwards.
+    pub fn value_ptr(self: &Self) -> *const fvalue_t {
+        #[cfg(not(ws_ge_4_0))]
+        return &self.value as *const fvalue_t;
+        #[cfg(ws_ge_4_0)]
+        return self.value as *const fvalue_t;
+    }
 }
 
+#[cfg(not(feature = "bindgen"))]
 #[repr(C)]
 pub struct proto_plugin {
     pub register_protoinfo: Option<extern "C" fn()>, /* routine to call to register protocol information */
     pub register_handoff: Option<extern "C" fn()>,   /* routine to call to register protocol information */
 }
 
+#[cfg(not(feature = "bindgen"))]
 impl Default for proto_plugin {
     fn default() -> Self {
         proto_plugin {
@@ -238,9 +356,9 @@ impl Default for proto_plugin {
 #[repr(transparent)]
 pub struct ETTIndex(pub i32);
 
+dynamic_extern! {
 #[link(name = "wireshark")]
 extern "C" {
-
     // proto register
     pub fn proto_register_protocol(
         name: *const libc::c_char,
@@ -253,46 +371,60 @@ extern "C" {
     pub fn proto_register_subtree_array(indices: *mut *mut ETTIndex, num_indices: i32);
 
     // Proto tree
-    pub fn proto_tree_add_protocol_format(
+    pub fn proto_tree_add_item(
         tree: *mut proto_tree,
         hfindex: HFIndex,
         tvb: *mut tvbuff_t,
         start: i32,
         length: i32,
-        format: *const libc::c_char,
-        ...
+        encoding: Encoding,
     ) -> *mut proto_item;
 
-    pub fn proto_tree_add_item(
+    pub fn proto_tree_add_item_ret_int(
         tree: *mut proto_tree,
         hfindex: HFIndex,
         tvb: *mut tvbuff_t,
         start: i32,
         length: i32,
         encoding: Encoding,
+        retval: *mut i32,
     ) -> *mut proto_item;
 
-    pub fn proto_tree_add_item_ret_int(
+    pub fn proto_tree_add_item_ret_uint64(
         tree: *mut proto_tree,
         hfindex: HFIndex,
         tvb: *mut tvbuff_t,
         start: i32,
         length: i32,
         encoding: Encoding,
-        retval: *mut i32,
+        retval: *mut u64,
     ) -> *mut proto_item;
 
-    pub fn proto_tree_add_item_ret_uint64(
+    pub fn proto_tree_add_item_ret_uint(
         tree: *mut proto_tree,
         hfindex: HFIndex,
         tvb: *mut tvbuff_t,
         start: i32,
         length: i32,
         encoding: Encoding,
-        retval: *mut u64,
+        retval: *mut u32,
     ) -> *mut proto_item;
 
-    pub fn proto_tree_add_item_ret_uint(
+    // The bitmask of the field is applied to these, older releases only have the 32 bit and unsigned functions.
+    #[cfg(ws_ge_3_0)]
+    pub fn proto_tree_add_item_ret_int64(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut i64,
+    ) -> *mut proto_item;
+
+    // The address is returned in network order.
+    #[cfg(ws_ge_3_0)]
+    pub fn proto_tree_add_item_ret_ipv4(
         tree: *mut proto_tree,
         hfindex: HFIndex,
         tvb: *mut tvbuff_t,
@@ -302,12 +434,312 @@ extern "C" {
         retval: *mut u32,
     ) -> *mut proto_item;
 
+    #[cfg(ws_ge_4_0)]
+    pub fn proto_tree_add_item_ret_ipv6(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut [u8; 16],
+    ) -> *mut proto_item;
+
+    // The retval holds the six bytes of the address.
+    #[cfg(ws_ge_4_0)]
+    pub fn proto_tree_add_item_ret_ether(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut u8,
+    ) -> *mut proto_item;
+
+    #[cfg(ws_ge_4_0)]
+    pub fn proto_tree_add_item_ret_float(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut f32,
+    ) -> *mut proto_item;
+
+    #[cfg(ws_ge_4_0)]
+    pub fn proto_tree_add_item_ret_double(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut f64,
+    ) -> *mut proto_item;
+
+    pub fn proto_tree_add_item_ret_boolean(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut BoolValue,
+    ) -> *mut proto_item;
+
+    // The string is allocated in scope, it is converted to UTF-8, lenretval is the length in the tvb.
+    pub fn proto_tree_add_item_ret_string_and_length(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        scope: *mut wmem_allocator_t,
+        retval: *mut *const u8,
+        lenretval: *mut i32,
+    ) -> *mut proto_item;
+
+    // Decodes any of the time encodings, endoff and err may be null, err is only set for string encodings.
+    pub fn proto_tree_add_time_item(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        encoding: Encoding,
+        retval: *mut nstime_t,
+        endoff: *mut i32,
+        err: *mut i32,
+    ) -> *mut proto_item;
+
+    // Add items with a value from Rust, instead of reading it from the tvb, the tvb range is highlighted for them.
+    pub fn proto_tree_add_uint(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: u32,
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_int(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: i32,
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_uint64(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: u64,
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_int64(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: i64,
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_string(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: *const libc::c_char,
+    ) -> *mut proto_item;
+    // The value holds length bytes.
+    pub fn proto_tree_add_bytes(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        start_ptr: *const u8,
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_double(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: f64,
+    ) -> *mut proto_item;
+    pub fn proto_tree_add_boolean(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        value: BooleanValue,
+    ) -> *mut proto_item;
+
+    // Look up the registered field, throws if the index isn't registered, check it against proto_registrar_n first.
+    pub fn proto_registrar_get_nth(hfindex: u32) -> *mut header_field_info;
+    pub fn proto_registrar_n() -> i32;
+
     // Proto item functions below
-    pub fn proto_item_set_text(ti: *mut proto_item, text: *const libc::c_char);
-    pub fn proto_item_append_text(ti: *mut proto_item, text: *const libc::c_char);
-    pub fn proto_item_prepend_text(ti: *mut proto_item, text: *const libc::c_char);
     pub fn proto_item_add_subtree(ti: *mut proto_item, ett_id: ETTIndex) -> *mut proto_tree;
 
     // Introspection
     pub fn proto_all_finfos(tree: *mut proto_tree) -> *mut GPtrArray;
 }
+}
+
+// Variadic functions can't go through dynamic_extern, with the dynamic feature these take a single argument for the
+// format, which is all we pass anyway.
+#[cfg(not(feature = "dynamic"))]
+#[link(name = "wireshark")]
+extern "C" {
+    pub fn proto_tree_add_protocol_format(
+        tree: *mut proto_tree,
+        hfindex: HFIndex,
+        tvb: *mut tvbuff_t,
+        start: i32,
+        length: i32,
+        format: *const libc::c_char,
+        ...
+    ) -> *mut proto_item;
+}
+
+#[cfg(feature = "dynamic")]
+pub unsafe fn proto_tree_add_protocol_format(
+    tree: *mut proto_tree,
+    hfindex: HFIndex,
+    tvb: *mut tvbuff_t,
+    start: i32,
+    length: i32,
+    format: *const libc::c_char,
+    argument: *const libc::c_char,
+) -> *mut proto_item {
+    static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new("proto_tree_add_protocol_format\0");
+    let function: unsafe extern "C" fn(
+        *mut proto_tree,
+        HFIndex,
+        *mut tvbuff_t,
+        i32,
+        i32,
+        *const libc::c_char,
+        ...
+    ) -> *mut proto_item = std::mem::transmute(SYMBOL.get());
+    return function(tree, hfindex, tvb, start, length, format, argument);
+}
+
+// The text functions of an item take a format too, the text is passed as its argument.
+macro_rules! item_text_functions {
+    ($($name:ident;)*) => {
+        #[cfg(not(feature = "dynamic"))]
+        #[link(name = "wireshark")]
+        extern "C" {
+            $(
+                pub fn $name(ti: *mut proto_item, format: *const libc::c_char, ...);
+            )*
+        }
+
+        $(
+            #[cfg(feature = "dynamic")]
+            pub unsafe fn $name(ti: *mut proto_item, format: *const libc::c_char, argument: *const libc::c_char) {
+                static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new(concat!(stringify!($name), "\0"));
+                let function: unsafe extern "C" fn(*mut proto_item, *const libc::c_char, ...) =
+                    std::mem::transmute(SYMBOL.get());
+                function(ti, format, argument);
+            }
+        )*
+    };
+}
+
+item_text_functions! {
+    proto_item_set_text;
+    proto_item_append_text;
+    proto_item_prepend_text;
+}
+
+/// The value proto_tree_add_item_ret_boolean returns, a gboolean before 4.2 and a bool from 4.2 on.
+#[cfg(not(ws_ge_4_2))]
+pub type BoolValue = i32;
+#[cfg(ws_ge_4_2)]
+pub type BoolValue = bool;
+
+/// The value of a boolean became 64 bits in 4.2.
+#[cfg(not(ws_ge_4_2))]
+pub type BooleanValue = u32;
+#[cfg(ws_ge_4_2)]
+pub type BooleanValue = u64;
+
+// The _format_value functions only differ in the type of the value, the format is used for the value, the field name
+// is still shown in front of it.
+macro_rules! format_value_functions {
+    ($($name:ident($value:ty);)*) => {
+        #[cfg(not(feature = "dynamic"))]
+        #[link(name = "wireshark")]
+        extern "C" {
+            $(
+                pub fn $name(
+                    tree: *mut proto_tree,
+                    hfindex: HFIndex,
+                    tvb: *mut tvbuff_t,
+                    start: i32,
+                    length: i32,
+                    value: $value,
+                    format: *const libc::c_char,
+                    ...
+                ) -> *mut proto_item;
+            )*
+        }
+
+        $(
+            #[cfg(feature = "dynamic")]
+            pub unsafe fn $name(
+                tree: *mut proto_tree,
+                hfindex: HFIndex,
+                tvb: *mut tvbuff_t,
+                start: i32,
+                length: i32,
+                value: $value,
+                format: *const libc::c_char,
+                argument: *const libc::c_char,
+            ) -> *mut proto_item {
+                static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new(concat!(stringify!($name), "\0"));
+                let function: unsafe extern "C" fn(
+                    *mut proto_tree,
+                    HFIndex,
+                    *mut tvbuff_t,
+                    i32,
+                    i32,
+                    $value,
+                    *const libc::c_char,
+                    ...
+                ) -> *mut proto_item = std::mem::transmute(SYMBOL.get());
+                return function(tree, hfindex, tvb, start, length, value, format, argument);
+            }
+        )*
+
+        #[cfg(feature = "dynamic")]
+        pub(crate) const VARIADIC_SYMBOLS: &[&str] = &[
+            "proto_tree_add_protocol_format\0",
+            "proto_item_set_text\0",
+            "proto_item_append_text\0",
+            "proto_item_prepend_text\0",
+            $(concat!(stringify!($name), "\0"),)*
+        ];
+    };
+}
+
+format_value_functions! {
+    proto_tree_add_uint_format_value(u32);
+    proto_tree_add_int_format_value(i32);
+    proto_tree_add_uint64_format_value(u64);
+    proto_tree_add_int64_format_value(i64);
+    proto_tree_add_string_format_value(*const libc::c_char);
+    proto_tree_add_bytes_format_value(*const u8);
+    proto_tree_add_double_format_value(f64);
+    proto_tree_add_boolean_format_value(BooleanValue);
+}
@@ -0,0 +1,22 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use super::packet_info::packet_info;
+use super::wmem::wmem_allocator_t;
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Data stored against the frame by protocol and key, with the file scope it survives dissecting the frame again.
+    // Adding with a key that is already used hides the earlier data, it isn't freed.
+    pub fn p_add_proto_data(
+        scope: *mut wmem_allocator_t,
+        pinfo: *mut packet_info,
+        proto: i32,
+        key: u32,
+        proto_data: *mut libc::c_void,
+    );
+    pub fn p_get_proto_data(scope: *mut wmem_allocator_t, pinfo: *mut packet_info, proto: i32, key: u32)
+        -> *mut libc::c_void;
+}
+}
@@ -1,6 +1,8 @@
 // Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
 // SPDX-License-Identifier: GPL-2.0-or-later
 
+use super::wmem::wmem_allocator_t;
+
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct range_admin_t {
@@ -22,3 +24,24 @@ impl Default for epan_range {
         }
     }
 }
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum convert_ret_t {
+    CVT_NO_ERROR,
+    CVT_SYNTAX_ERROR,
+    CVT_NUMBER_TOO_BIG,
+}
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Parses a range string like "1-5,7", the range is allocated in scope, or with g_malloc if that's null.
+    pub fn range_convert_str(
+        scope: *mut wmem_allocator_t,
+        range: *mut *mut epan_range,
+        es: *const libc::c_char,
+        max_value: u32,
+    ) -> convert_ret_t;
+}
+}
@@ -0,0 +1,155 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use super::packet_info::packet_info;
+use super::proto::{proto_tree, ETTIndex, HFIndex};
+use super::tvbuff::tvbuff_t;
+
+/// A table holding the fragments and the reassembled data, wireshark fills it in, it must persist.
+#[repr(C)]
+#[derive(Debug)]
+pub struct reassembly_table {
+    pub fragment_table: *mut libc::c_void,
+    pub reassembled_table: *mut libc::c_void,
+    pub temporary_key_func: *mut libc::c_void,
+    pub persistent_key_func: *mut libc::c_void,
+    pub free_temporary_key_func: *mut libc::c_void,
+}
+
+impl Default for reassembly_table {
+    fn default() -> Self {
+        reassembly_table {
+            fragment_table: std::ptr::null_mut(),
+            reassembled_table: std::ptr::null_mut(),
+            temporary_key_func: std::ptr::null_mut(),
+            persistent_key_func: std::ptr::null_mut(),
+            free_temporary_key_func: std::ptr::null_mut(),
+        }
+    }
+}
+
+/// The functions that make the keys fragments are matched by, we only use the ones wireshark provides.
+#[repr(C)]
+pub struct reassembly_table_functions {
+    _private: [u8; 0],
+}
+
+// Differs between releases, we only ever pass pointers to it around.
+#[repr(C)]
+pub struct fragment_head {
+    _private: [u8; 0],
+}
+
+/// The fields and subtrees used to show the fragments, all these must persist.
+#[repr(C)]
+#[derive(Debug)]
+pub struct fragment_items {
+    pub ett_fragment: *mut ETTIndex,
+    pub ett_fragments: *mut ETTIndex,
+
+    pub hf_fragments: *mut HFIndex,                  // FT_NONE
+    pub hf_fragment: *mut HFIndex,                   // FT_FRAMENUM
+    pub hf_fragment_overlap: *mut HFIndex,           // FT_BOOLEAN
+    pub hf_fragment_overlap_conflict: *mut HFIndex,  // FT_BOOLEAN
+    pub hf_fragment_multiple_tails: *mut HFIndex,    // FT_BOOLEAN
+    pub hf_fragment_too_long_fragment: *mut HFIndex, // FT_BOOLEAN
+    pub hf_fragment_error: *mut HFIndex,             // FT_FRAMENUM
+    pub hf_fragment_count: *mut HFIndex,             // FT_UINT32
+    pub hf_reassembled_in: *mut HFIndex,             // FT_FRAMENUM
+    pub hf_reassembled_length: *mut HFIndex,         // FT_UINT32
+    pub hf_reassembled_data: *mut HFIndex,           // FT_BYTES
+
+    pub tag: *const libc::c_char,
+}
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Register a table, wireshark initialises and clears it whenever a capture file is opened or closed.
+    pub fn reassembly_table_register(table: *mut reassembly_table, funcs: *const reassembly_table_functions);
+
+    // Add a fragment, by its offset in bytes. Returns the head of the fragments if the data is complete.
+    pub fn fragment_add_check(
+        table: *mut reassembly_table,
+        tvb: *mut tvbuff_t,
+        offset: i32,
+        pinfo: *const packet_info,
+        id: u32,
+        data: *const libc::c_void,
+        frag_offset: u32,
+        frag_data_len: u32,
+        more_frags: i32,
+    ) -> *mut fragment_head;
+
+    // Add a fragment, by its sequence number. Returns the head of the fragments if the data is complete.
+    pub fn fragment_add_seq_check(
+        table: *mut reassembly_table,
+        tvb: *mut tvbuff_t,
+        offset: i32,
+        pinfo: *const packet_info,
+        id: u32,
+        data: *const libc::c_void,
+        frag_number: u32,
+        frag_data_len: u32,
+        more_frags: i32,
+    ) -> *mut fragment_head;
+
+    // Adds the fragment tree, if the data was reassembled in this frame it returns the reassembled tvb, which is
+    // added as a data source with this name. Otherwise it adds where it was reassembled in and returns null.
+    pub fn process_reassembled_data(
+        tvb: *mut tvbuff_t,
+        offset: i32,
+        pinfo: *mut packet_info,
+        name: *const libc::c_char,
+        fd_head: *mut fragment_head,
+        fit: *const fragment_items,
+        update_col_infop: *mut i32,
+        tree: *mut proto_tree,
+    ) -> *mut tvbuff_t;
+}
+}
+
+// Key by the source and destination addresses and the id.
+#[cfg(not(feature = "dynamic"))]
+pub fn addresses_reassembly_table_functions() -> *const reassembly_table_functions {
+    unsafe {
+        return &data::addresses_reassembly_table_functions;
+    }
+}
+
+// Key by the source and destination addresses and ports and the id.
+#[cfg(not(feature = "dynamic"))]
+pub fn addresses_ports_reassembly_table_functions() -> *const reassembly_table_functions {
+    unsafe {
+        return &data::addresses_ports_reassembly_table_functions;
+    }
+}
+
+#[cfg(not(feature = "dynamic"))]
+mod data {
+    use super::reassembly_table_functions;
+    #[link(name = "wireshark")]
+    extern "C" {
+        pub static addresses_reassembly_table_functions: reassembly_table_functions;
+        pub static addresses_ports_reassembly_table_functions: reassembly_table_functions;
+    }
+}
+
+#[cfg(feature = "dynamic")]
+pub fn addresses_reassembly_table_functions() -> *const reassembly_table_functions {
+    static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new(DATA_SYMBOLS[0]);
+    return SYMBOL.get() as *const reassembly_table_functions;
+}
+
+#[cfg(feature = "dynamic")]
+pub fn addresses_ports_reassembly_table_functions() -> *const reassembly_table_functions {
+    static SYMBOL: super::dynamic::Symbol = super::dynamic::Symbol::new(DATA_SYMBOLS[1]);
+    return SYMBOL.get() as *const reassembly_table_functions;
+}
+
+/// The variables above aren't functions, but they're looked up the same way.
+#[cfg(feature = "dynamic")]
+pub(crate) const DATA_SYMBOLS: &[&str] = &[
+    "addresses_reassembly_table_functions\0",
+    "addresses_ports_reassembly_table_functions\0",
+];
@@ -6,6 +6,7 @@ pub struct tvbuff_t {
     _private: [u8; 0],
 }
 
+dynamic_extern! {
 #[link(name = "wireshark")]
 extern "C" {
     // This function comes with the fatest warning ever...
@@ -23,4 +24,8 @@ extern "C" {
      * target memory. */
     pub fn tvb_memcpy(tvb: *const tvbuff_t, target: *mut libc::c_void, offset: i32, length: usize)
         -> *mut libc::c_void;
+
+    // Create a tvb that starts at backing_offset and holds the remainder of the backing tvb.
+    pub fn tvb_new_subset_remaining(backing: *mut tvbuff_t, backing_offset: i32) -> *mut tvbuff_t;
+}
 }
@@ -0,0 +1,21 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+#[repr(C)]
+pub struct wmem_allocator_t {
+    _private: [u8; 0],
+}
+
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    // Memory that lives as long as epan, used for things like preferences.
+    pub fn wmem_epan_scope() -> *mut wmem_allocator_t;
+
+    // Memory that lives until the capture file is closed, only valid while a file is open.
+    pub fn wmem_file_scope() -> *mut wmem_allocator_t;
+
+    // Memory that lives until the packet is dissected.
+    pub fn wmem_packet_scope() -> *mut wmem_allocator_t;
+}
+}
@@ -0,0 +1,37 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use super::wmem::wmem_allocator_t;
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum wmem_cb_event_t {
+    /// The memory of the allocator was freed, like the file scope when a capture is closed.
+    WMEM_CB_FREE_EVENT,
+    /// The allocator itself is destroyed.
+    WMEM_CB_DESTROY_EVENT,
+}
+
+/// Return false to unregister the callback after it has been called.
+pub type wmem_user_cb_t = Option<
+    unsafe extern "C" fn(allocator: *mut wmem_allocator_t, event: wmem_cb_event_t, user_data: *mut libc::c_void) -> i32,
+>;
+
+// wmem moved from libwireshark into libwsutil in 3.6.
+#[cfg(not(ws_ge_3_6))]
+dynamic_extern! {
+#[link(name = "wireshark")]
+extern "C" {
+    pub fn wmem_register_callback(allocator: *mut wmem_allocator_t, callback: wmem_user_cb_t, user_data: *mut libc::c_void)
+        -> u32;
+}
+}
+
+#[cfg(ws_ge_3_6)]
+dynamic_extern! {
+#[link(name = "wsutil")]
+extern "C" {
+    pub fn wmem_register_callback(allocator: *mut wmem_allocator_t, callback: wmem_user_cb_t, user_data: *mut libc::c_void)
+        -> u32;
+}
+}
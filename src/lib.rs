@@ -11,6 +11,10 @@ pub mod dissector;
 /// for some of the types. File structure mirrors that of the wireshark headers.
 pub mod epan;
 
+/// Holds the version of wireshark this crate was built against and the [`wireshark_plugin!`] macro that exports the
+/// symbols wireshark needs to load the plugin.
+pub mod version;
+
 /// This module exposes a single plugin method and holds several C functions that are registered and subsequently call
 /// into the Dissector object the user provided.
 mod plugin;
@@ -4,6 +4,7 @@
 use crate::dissector;
 use crate::epan;
 use crate::util;
+use crate::version;
 
 use crate::dissector::Dissector;
 use crate::dissector::PacketField;
@@ -17,115 +18,471 @@ fn string_container_to_perm(v: &dissector::StringContainer) -> *const libc::c_ch
 
 impl From<PacketField> for epan::proto::header_field_info {
     fn from(field: PacketField) -> Self {
-        epan::proto::header_field_info {
-            name: string_container_to_perm(&field.name),
-            abbrev: string_container_to_perm(&field.abbrev),
-            type_: field.field_type.into(),
-            display: field.display.into(),
-            ..Default::default()
-        }
+        epan::proto::header_field_info::new(
+            string_container_to_perm(&field.name),
+            string_container_to_perm(&field.abbrev),
+            field.field_type.into(),
+            field.display,
+        )
     }
 }
 
-use std::rc::Rc;
-// Global state
-static mut DISSECTOR_PTR: Option<Rc<dyn Dissector>> = None;
-static mut HF_ENTRIES: Option<Vec<epan::proto::hf_register_info>> = None;
-static mut PROTO_ID: i32 = -1; // Todo? change into a newtype.
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::OnceLock;
+
+/// The maximum number of dissectors that can be registered from a single plugin, one for each set of trampolines.
+pub const MAX_DISSECTORS: usize = 16;
+
+/// The associated conversation state type makes [`Dissector`] unusable as a trait object. This trait holds what the
+/// plugin needs from a dissector, dissection takes the protocol id instead of the typed conversation.
+trait PluginDissector: Send + Sync {
+    fn get_fields(self: &Self) -> Vec<PacketField>;
+    fn set_field_indices(self: &mut Self, hf_indices: Vec<(PacketField, epan::proto::HFIndex)>);
+    fn get_tree_count(self: &Self) -> usize;
+    fn set_tree_indices(self: &mut Self, ett_indices: Vec<epan::proto::ETTIndex>);
+    fn get_protocol_name(self: &Self) -> (&'static str, &'static str, &'static str);
+    fn get_dissector_names(self: &Self) -> Vec<&'static str>;
+    fn get_registration(self: &Self) -> Vec<dissector::Registration>;
+    fn get_dissector_tables(self: &Self) -> Vec<dissector::DissectorTableDefinition>;
+    fn set_dissector_tables(self: &mut Self, tables: Vec<(dissector::DissectorTableDefinition, epan::DissectorTable)>);
+    fn get_heuristic_lists(self: &Self) -> Vec<&'static str>;
+    fn set_heuristic_lists(self: &mut Self, lists: Vec<(&'static str, epan::HeuristicList)>);
+    fn get_preferences(self: &Self) -> Vec<dissector::PreferenceDefinition>;
+    fn set_preferences(self: &mut Self, preferences: Vec<(dissector::PreferenceDefinition, epan::Preference)>);
+    fn preferences_changed(self: &Self);
+    fn get_expert_fields(self: &Self) -> Vec<dissector::ExpertField>;
+    fn set_expert_indices(self: &mut Self, indices: Vec<(dissector::ExpertField, epan::expert::ExpertIndex)>);
+    fn get_reassembly_tables(self: &Self) -> Vec<dissector::ReassemblyTableDefinition>;
+    fn set_reassembly_tables(
+        self: &mut Self,
+        tables: Vec<(dissector::ReassemblyTableDefinition, epan::ReassemblyTable)>,
+    );
+    fn dissect(
+        self: &Self,
+        pinfo: &mut epan::PacketInfo,
+        proto_id: i32,
+        proto: &mut epan::ProtoTree,
+        tvb: &mut epan::TVB,
+    ) -> usize;
+    fn heuristic_dissect(
+        self: &Self,
+        pinfo: &mut epan::PacketInfo,
+        proto_id: i32,
+        proto: &mut epan::ProtoTree,
+        tvb: &mut epan::TVB,
+    ) -> bool;
+}
+
+impl<T: Dissector> PluginDissector for T {
+    fn get_fields(self: &Self) -> Vec<PacketField> {
+        Dissector::get_fields(self)
+    }
+    fn set_field_indices(self: &mut Self, hf_indices: Vec<(PacketField, epan::proto::HFIndex)>) {
+        Dissector::set_field_indices(self, hf_indices)
+    }
+    fn get_tree_count(self: &Self) -> usize {
+        Dissector::get_tree_count(self)
+    }
+    fn set_tree_indices(self: &mut Self, ett_indices: Vec<epan::proto::ETTIndex>) {
+        Dissector::set_tree_indices(self, ett_indices)
+    }
+    fn get_protocol_name(self: &Self) -> (&'static str, &'static str, &'static str) {
+        Dissector::get_protocol_name(self)
+    }
+    fn get_dissector_names(self: &Self) -> Vec<&'static str> {
+        Dissector::get_dissector_names(self)
+    }
+    fn get_registration(self: &Self) -> Vec<dissector::Registration> {
+        Dissector::get_registration(self)
+    }
+    fn get_dissector_tables(self: &Self) -> Vec<dissector::DissectorTableDefinition> {
+        Dissector::get_dissector_tables(self)
+    }
+    fn set_dissector_tables(self: &mut Self, tables: Vec<(dissector::DissectorTableDefinition, epan::DissectorTable)>) {
+        Dissector::set_dissector_tables(self, tables)
+    }
+    fn get_heuristic_lists(self: &Self) -> Vec<&'static str> {
+        Dissector::get_heuristic_lists(self)
+    }
+    fn set_heuristic_lists(self: &mut Self, lists: Vec<(&'static str, epan::HeuristicList)>) {
+        Dissector::set_heuristic_lists(self, lists)
+    }
+    fn get_preferences(self: &Self) -> Vec<dissector::PreferenceDefinition> {
+        Dissector::get_preferences(self)
+    }
+    fn set_preferences(self: &mut Self, preferences: Vec<(dissector::PreferenceDefinition, epan::Preference)>) {
+        Dissector::set_preferences(self, preferences)
+    }
+    fn preferences_changed(self: &Self) {
+        Dissector::preferences_changed(self)
+    }
+    fn get_expert_fields(self: &Self) -> Vec<dissector::ExpertField> {
+        Dissector::get_expert_fields(self)
+    }
+    fn set_expert_indices(self: &mut Self, indices: Vec<(dissector::ExpertField, epan::expert::ExpertIndex)>) {
+        Dissector::set_expert_indices(self, indices)
+    }
+    fn get_reassembly_tables(self: &Self) -> Vec<dissector::ReassemblyTableDefinition> {
+        Dissector::get_reassembly_tables(self)
+    }
+    fn set_reassembly_tables(
+        self: &mut Self,
+        tables: Vec<(dissector::ReassemblyTableDefinition, epan::ReassemblyTable)>,
+    ) {
+        Dissector::set_reassembly_tables(self, tables)
+    }
+    fn dissect(
+        self: &Self,
+        pinfo: &mut epan::PacketInfo,
+        proto_id: i32,
+        proto: &mut epan::ProtoTree,
+        tvb: &mut epan::TVB,
+    ) -> usize {
+        let mut conversation = unsafe { epan::Conversation::from_ptr(pinfo.into(), proto_id) };
+        Dissector::dissect(self, pinfo, &mut conversation, proto, tvb)
+    }
+    fn heuristic_dissect(
+        self: &Self,
+        pinfo: &mut epan::PacketInfo,
+        proto_id: i32,
+        proto: &mut epan::ProtoTree,
+        tvb: &mut epan::TVB,
+    ) -> bool {
+        let mut conversation = unsafe { epan::Conversation::from_ptr(pinfo.into(), proto_id) };
+        Dissector::heuristic_dissect(self, pinfo, &mut conversation, proto, tvb)
+    }
+}
+
+/// A dissector that completed protocol registration, from here on it is only ever accessed through a shared reference.
+struct RegisteredDissector {
+    dissector: Box<dyn PluginDissector>,
+    proto_id: i32, // Todo? change into a newtype.
+    handle: epan::DissectorHandle,
+}
+
+// Global state, the index into these is the index of the trampolines used for that dissector.
+// Dissectors are owned by PENDING between setup and protocol registration, after which they move into REGISTERED.
+static PENDING: Mutex<Vec<Option<Box<dyn PluginDissector>>>> = Mutex::new(Vec::new());
+#[allow(clippy::declare_interior_mutable_const)]
+const UNREGISTERED: OnceLock<RegisteredDissector> = OnceLock::new();
+static REGISTERED: [OnceLock<RegisteredDissector>; MAX_DISSECTORS] = [UNREGISTERED; MAX_DISSECTORS];
+
+/// Lock the pending dissectors, a panic while holding the lock doesn't invalidate the vector, so ignore poisoning.
+fn pending() -> MutexGuard<'static, Vec<Option<Box<dyn PluginDissector>>>> {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner())
+}
 
-/// Actual implementation of setup that stores the passed in dissector into the global singleton.
-pub fn setup<T: 'static + Dissector>(d: Rc<T>) {
+/// Retrieve the dissector in a slot, returns None if it didn't complete registration.
+fn registered(index: usize) -> Option<&'static RegisteredDissector> {
+    REGISTERED.get(index).and_then(|v| v.get())
+}
+
+/// Wireshark's callbacks don't provide a way to pass user data, so we instantiate the C functions once for every slot
+/// in the dissector vector, the const generic parameter is then used to find the right dissector.
+struct Trampolines {
+    register_protoinfo: extern "C" fn(),
+    register_handoff: extern "C" fn(),
+    dissect: epan::packet::dissector_fn,
+    heuristic: epan::packet::heur_dissector_fn,
+    apply_preferences: epan::prefs::prefs_apply_fn,
+}
+
+macro_rules! make_trampolines {
+    ($($index:literal),*) => {
+        [$(Trampolines {
+            register_protoinfo: proto_register_protoinfo::<$index>,
+            register_handoff: proto_register_handoff::<$index>,
+            dissect: dissect_protocol_function::<$index>,
+            heuristic: heuristic_dissector_function::<$index>,
+            apply_preferences: preferences_apply::<$index>,
+        }),*]
+    };
+}
+
+static TRAMPOLINES: [Trampolines; MAX_DISSECTORS] =
+    make_trampolines!(0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+
+/// Report a problem with the registration, plugins have no stderr on most platforms so this goes to wireshark's log.
+fn report(message: &str) {
+    let message = util::c_string(message);
     unsafe {
-        // Register our two global functions.
-        let mut plugin_handle_box: Box<epan::proto::proto_plugin> = Box::new(Default::default());
-        plugin_handle_box.register_protoinfo = Some(proto_register_protoinfo);
-        plugin_handle_box.register_handoff = Some(proto_register_handoff);
-        let ptr_to_plugin = Box::leak(plugin_handle_box); // Need this to persist, but we don't ever need it anymore
-        epan::proto::proto_register_plugin(ptr_to_plugin);
+        epan::glib::g_log(
+            util::perm_string_ptr("wireshark_dissector_rs"),
+            epan::glib::G_LOG_LEVEL_WARNING,
+            util::perm_string_ptr("%s"),
+            message.as_ptr(),
+        );
+    }
+}
+
+/// Actual implementation of setup that takes ownership of the dissector until wireshark registers its protocol.
+pub fn setup<T: 'static + Dissector>(d: T) {
+    let d: Box<dyn PluginDissector> = Box::new(d);
+
+    // If the functions are looked up at runtime the wireshark we're loaded into may not have all of them.
+    let missing = epan::dynamic::missing_symbols();
+    if !missing.is_empty() {
+        report(&format!(
+            "Wireshark lacks the functions {}, not registering {}.",
+            missing.join(", "),
+            d.get_protocol_name().0
+        ));
+        return;
+    }
+
+    // Structs differ between releases, using them with another release would corrupt memory.
+    match version::running() {
+        Some(running) if version::is_compatible(running) => {}
+        running => {
+            let running = running
+                .map(|(major, minor, micro)| format!("{}.{}.{}", major, minor, micro))
+                .unwrap_or_else(|| String::from("an unknown version"));
+            report(&format!(
+                "{} was built for wireshark {}, but this is wireshark {}, not registering it.",
+                d.get_protocol_name().0,
+                version::VERSION,
+                running
+            ));
+            return;
+        }
+    }
 
+    let index = {
+        let mut pending = pending();
+        if pending.len() >= MAX_DISSECTORS {
+            report(&format!(
+                "Can't register more than {} dissectors from one plugin, ignoring {}.",
+                MAX_DISSECTORS,
+                d.get_protocol_name().0
+            ));
+            return;
+        }
         // store the dissector we got handed in.
-        DISSECTOR_PTR = Some(d);
+        pending.push(Some(d));
+        pending.len() - 1
+    };
+
+    // Register the two functions for this slot.
+    let plugin_handle_box = Box::new(epan::proto::proto_plugin {
+        register_protoinfo: Some(TRAMPOLINES[index].register_protoinfo),
+        register_handoff: Some(TRAMPOLINES[index].register_handoff),
+    });
+    let ptr_to_plugin = Box::leak(plugin_handle_box); // Need this to persist, but we don't ever need it anymore
+    unsafe {
+        epan::proto::proto_register_plugin(ptr_to_plugin);
     }
 }
 
-/// Global dissection function that retrieves the dissector from the singleton, calls dissect and returns it.
-extern "C" fn dissect_protocol_function(
+/// Dissection function for the dissector in slot `INDEX`, retrieves the dissector, calls dissect and returns it.
+extern "C" fn dissect_protocol_function<const INDEX: usize>(
     tvb: *mut epan::tvbuff::tvbuff_t,
-    _packet_info: *mut epan::packet_info::packet_info,
+    packet_info: *mut epan::packet_info::packet_info,
     tree: *mut epan::proto::proto_tree,
     _data: *mut libc::c_void,
 ) -> i32 {
+    // Retrieve our dissector, if it isn't registered there's nothing we can do.
+    let entry = match registered(INDEX) {
+        Some(entry) => entry,
+        None => return 0,
+    };
+
     // Create our nice safe wrappers
-    let mut proto: epan::ProtoTree = unsafe { epan::ProtoTree::from_ptr(tree) };
+    let mut pinfo: epan::PacketInfo = unsafe { epan::PacketInfo::with_proto_id(packet_info, entry.proto_id) };
+    let mut proto: epan::ProtoTree = unsafe { epan::ProtoTree::from_ptrs(tree, packet_info) };
     let mut tvb: epan::TVB = unsafe { epan::TVB::from_ptr(tvb) };
 
-    // A temporary to hold the,  we retrieve from a mutable static, so it's unsafe.
-    let dissector_tmp = unsafe { &DISSECTOR_PTR.as_ref().unwrap() };
-
     // Call the dissector.
-    let used_bytes = dissector_tmp.dissect(&mut proto, &mut tvb);
+    let used_bytes = entry
+        .dissector
+        .dissect(&mut pinfo, entry.proto_id, &mut proto, &mut tvb);
 
     // Return how much bytes we consumed.
     return used_bytes as i32;
 }
 
-/// Global heuristic dissector function.
-extern "C" fn heuristic_dissector_function(
+/// Heuristic dissector function for the dissector in slot `INDEX`.
+extern "C" fn heuristic_dissector_function<const INDEX: usize>(
     tvb: *mut epan::tvbuff::tvbuff_t,
-    _packet_info: *mut epan::packet_info::packet_info,
+    packet_info: *mut epan::packet_info::packet_info,
     tree: *mut epan::proto::proto_tree,
     _data: *mut libc::c_void,
 ) -> bool {
-    // A temporary to hold the,  we retrieve from a mutable static, so it's unsafe.
-    let dissector_tmp = unsafe { &DISSECTOR_PTR.as_ref().unwrap() };
+    // Retrieve our dissector, if it isn't registered it can't claim this packet.
+    let entry = match registered(INDEX) {
+        Some(entry) => entry,
+        None => return false,
+    };
 
     // Make our objects and invoke the heuristic dissector method.
-    let mut proto: epan::ProtoTree = unsafe { epan::ProtoTree::from_ptr(tree) };
+    let mut pinfo: epan::PacketInfo = unsafe { epan::PacketInfo::with_proto_id(packet_info, entry.proto_id) };
+    let mut proto: epan::ProtoTree = unsafe { epan::ProtoTree::from_ptrs(tree, packet_info) };
     let mut tvb: epan::TVB = unsafe { epan::TVB::from_ptr(tvb) };
 
-    let applies = dissector_tmp.heuristic_dissect(&mut proto, &mut tvb);
+    let applies = entry
+        .dissector
+        .heuristic_dissect(&mut pinfo, entry.proto_id, &mut proto, &mut tvb);
 
     return applies;
 }
 
-/// Global function to register our protocol.
-extern "C" fn proto_register_protoinfo() {
-    // We're only called once, ensure we have our HF entries setup.
+/// Register a reassembly table and the fields and subtrees for its fragments, wireshark uses all of these while
+/// dissecting, so they're leaked.
+fn register_reassembly_table(
+    proto_id: i32,
+    definition: &dissector::ReassemblyTableDefinition,
+) -> epan::ReassemblyTable {
+    use epan::proto::FieldDisplay::{BASE_DEC, BASE_NONE};
+    use epan::FieldType::{BOOLEAN, BYTES, FRAMENUM, NONE, UINT32};
+    let name = definition.name;
+    let fields = [
+        (format!("{} fragments", name), "fragments", NONE, BASE_NONE),
+        (format!("{} fragment", name), "fragment", FRAMENUM, BASE_NONE),
+        (
+            format!("{} fragment overlap", name),
+            "fragment.overlap",
+            BOOLEAN,
+            BASE_NONE,
+        ),
+        (
+            format!("{} fragment overlapping with conflicting data", name),
+            "fragment.overlap.conflicts",
+            BOOLEAN,
+            BASE_NONE,
+        ),
+        (
+            format!("{} has multiple tail fragments", name),
+            "fragment.multiple_tails",
+            BOOLEAN,
+            BASE_NONE,
+        ),
+        (
+            format!("{} fragment too long", name),
+            "fragment.too_long_fragment",
+            BOOLEAN,
+            BASE_NONE,
+        ),
+        (
+            format!("{} defragmentation error", name),
+            "fragment.error",
+            FRAMENUM,
+            BASE_NONE,
+        ),
+        (format!("{} fragment count", name), "fragment.count", UINT32, BASE_DEC),
+        (String::from("Reassembled in"), "reassembled.in", FRAMENUM, BASE_NONE),
+        (
+            format!("Reassembled {} length", name),
+            "reassembled.length",
+            UINT32,
+            BASE_DEC,
+        ),
+        (
+            format!("Reassembled {} data", name),
+            "reassembled.data",
+            BYTES,
+            BASE_NONE,
+        ),
+    ];
+
+    // The fragment items point at these, so unlike the dissector's own indices they must persist.
+    let hf = Box::leak(Box::new([epan::proto::HFIndex(-1); 11]));
+    let ett = Box::leak(Box::new([epan::proto::ETTIndex(-1); 2]));
+
+    let hf_fields: Vec<epan::proto::hf_register_info> = fields
+        .iter()
+        .zip(hf.iter_mut())
+        .map(|((field_name, suffix, field_type, display), id)| {
+            epan::proto::hf_register_info::new(
+                id,
+                epan::proto::header_field_info::new(
+                    util::perm_string_ptr(field_name),
+                    util::perm_string_ptr(&format!("{}.{}", definition.abbrev, suffix)),
+                    (*field_type).into(),
+                    *display,
+                ),
+            )
+        })
+        .collect();
+    let hf_fields = Box::leak(hf_fields.into_boxed_slice());
+    let mut ett_pointers: Vec<*mut epan::proto::ETTIndex> = ett.iter_mut().map(|v| v as *mut _).collect();
     unsafe {
-        HF_ENTRIES = Some(Vec::new());
+        epan::proto::proto_register_field_array(proto_id, hf_fields.as_mut_ptr(), hf_fields.len() as i32);
+        epan::proto::proto_register_subtree_array(ett_pointers.as_mut_ptr(), ett_pointers.len() as i32);
     }
 
-    let dissector_tmp = unsafe { Rc::<dyn Dissector + 'static>::get_mut(DISSECTOR_PTR.as_mut().unwrap()).unwrap() };
+    let items = Box::leak(Box::new(epan::reassemble::fragment_items {
+        ett_fragment: &mut ett[0],
+        ett_fragments: &mut ett[1],
+        hf_fragments: &mut hf[0],
+        hf_fragment: &mut hf[1],
+        hf_fragment_overlap: &mut hf[2],
+        hf_fragment_overlap_conflict: &mut hf[3],
+        hf_fragment_multiple_tails: &mut hf[4],
+        hf_fragment_too_long_fragment: &mut hf[5],
+        hf_fragment_error: &mut hf[6],
+        hf_fragment_count: &mut hf[7],
+        hf_reassembled_in: &mut hf[8],
+        hf_reassembled_length: &mut hf[9],
+        hf_reassembled_data: &mut hf[10],
+        tag: util::perm_string_ptr(&format!("{} fragments", name)),
+    }));
+
+    let table = Box::leak(Box::new(epan::reassemble::reassembly_table::default()));
+    let functions = match definition.key {
+        dissector::ReassemblyKey::Addresses => epan::reassemble::addresses_reassembly_table_functions(),
+        dissector::ReassemblyKey::AddressesPorts => epan::reassemble::addresses_ports_reassembly_table_functions(),
+    };
+    unsafe {
+        epan::reassemble::reassembly_table_register(table, functions);
+        return epan::ReassemblyTable::from_ptr(table, items, util::perm_string_ptr(&format!("Reassembled {}", name)));
+    }
+}
 
-    // Make a vector to hold the HFIndex entries.
-    let mut field_ids: Vec<epan::proto::HFIndex> = Vec::new();
+/// Function to register the protocol of the dissector in slot `INDEX`.
+///
+/// This is the only phase in which the dissector is mutable, we own it exclusively here, it is moved into the
+/// registered dissectors at the end and only accessed through a shared reference from then on.
+extern "C" fn proto_register_protoinfo<const INDEX: usize>() {
+    let mut dissector_tmp = match pending().get_mut(INDEX).and_then(|v| v.take()) {
+        Some(d) => d,
+        None => return,
+    };
 
     // Obtain the fields we are about to register.
     let fields_input = dissector_tmp.get_fields();
-    unsafe {
-        // Register our protocol names and abbreviation.
-        let (full_name, short_name, filter_name) = dissector_tmp.get_protocol_name();
-        PROTO_ID = epan::proto::proto_register_protocol(
+
+    // The HFIndex entries, wireshark writes the registered index through p_id once and doesn't keep the pointer. The
+    // hf_register_info entries holding these pointers are leaked below though, so leak these with them instead of
+    // leaving dangling pointers in memory wireshark owns.
+    let field_ids = Box::leak(vec![epan::proto::HFIndex(-1); fields_input.len()].into_boxed_slice());
+
+    // Register our protocol names and abbreviation.
+    let (full_name, short_name, filter_name) = dissector_tmp.get_protocol_name();
+    let proto_id = unsafe {
+        epan::proto::proto_register_protocol(
             util::perm_string_ptr(full_name),
             util::perm_string_ptr(short_name),
             util::perm_string_ptr(filter_name),
-        );
-
-        // ok, here we get to make our header fields array, and then we can pass that to wireshark.
-        let hf_fields = &mut HF_ENTRIES.as_mut().unwrap();
+        )
+    };
+
+    // ok, here we get to make our header fields array, and then we can pass that to wireshark.
+    let mut hf_fields: Vec<epan::proto::hf_register_info> = Vec::new();
+
+    // Now, build the struct we're going to pass to wireshark.
+    for i in 0..fields_input.len() {
+        hf_fields.push(epan::proto::hf_register_info::new(
+            &mut field_ids[i],
+            fields_input[i].clone().into(),
+        ));
+    }
 
-        // Now, build the struct we're going to pass to wireshark.
-        field_ids.resize(fields_input.len(), epan::proto::HFIndex(-1));
-        for i in 0..fields_input.len() {
-            hf_fields.push(epan::proto::hf_register_info {
-                p_id: &mut field_ids[i],
-                hfinfo: fields_input[i].clone().into(),
-            });
+    // pass our struct to wireshark, it holds on to the header field info embedded in it, so this must persist.
+    if !hf_fields.is_empty() {
+        let hf_fields = Box::leak(hf_fields.into_boxed_slice());
+        unsafe {
+            epan::proto::proto_register_field_array(proto_id, hf_fields.as_mut_ptr(), hf_fields.len() as i32);
         }
-
-        // pass our struct to wireshark.
-        let rawptr = &mut hf_fields[0] as *mut epan::proto::hf_register_info;
-        epan::proto::proto_register_field_array(PROTO_ID, rawptr, hf_fields.len() as i32);
     }
 
     // And, then we assembly the return struct.
@@ -156,20 +513,193 @@ extern "C" fn proto_register_protoinfo() {
 
         dissector_tmp.set_tree_indices(ett_indices);
     }
+
+    // Register the reassembly tables, along with the fields and subtrees that show the fragments.
+    let reassembly_input = dissector_tmp.get_reassembly_tables();
+    if !reassembly_input.is_empty() {
+        let tables = reassembly_input
+            .iter()
+            .map(|definition| (definition.clone(), register_reassembly_table(proto_id, definition)))
+            .collect();
+        dissector_tmp.set_reassembly_tables(tables);
+    }
+
+    // Register the expert infos, like the fields wireshark holds on to these, so they must persist.
+    let experts_input = dissector_tmp.get_expert_fields();
+    if !experts_input.is_empty() {
+        let expert_ids = Box::leak(vec![epan::expert::expert_field::default(); experts_input.len()].into_boxed_slice());
+        let mut ei_fields: Vec<epan::expert::ei_register_info> = Vec::new();
+        for i in 0..experts_input.len() {
+            ei_fields.push(epan::expert::ei_register_info {
+                ids: &mut expert_ids[i],
+                eiinfo: epan::expert::expert_field_info {
+                    name: string_container_to_perm(&experts_input[i].abbrev),
+                    group: experts_input[i].group as i32,
+                    severity: experts_input[i].severity as i32,
+                    summary: string_container_to_perm(&experts_input[i].summary),
+                    id: 0,
+                    protocol: std::ptr::null(),
+                    orig_severity: 0,
+                    hf_info: epan::proto::hf_register_info::new(
+                        std::ptr::null_mut(),
+                        epan::proto::header_field_info::new(
+                            std::ptr::null(),
+                            std::ptr::null(),
+                            epan::ftypes::ftenum::NONE,
+                            epan::proto::FieldDisplay::BASE_NONE,
+                        ),
+                    ),
+                },
+            });
+        }
+        let ei_fields = Box::leak(ei_fields.into_boxed_slice());
+        unsafe {
+            let module = epan::expert::expert_register_protocol(proto_id);
+            epan::expert::expert_register_field_array(module, ei_fields.as_mut_ptr(), ei_fields.len() as i32);
+        }
+
+        let ei_indices = experts_input
+            .into_iter()
+            .zip(expert_ids.iter())
+            .map(|(field, id)| (field, epan::expert::ExpertIndex(*id)))
+            .collect();
+        dissector_tmp.set_expert_indices(ei_indices);
+    }
+
+    // Register the dissector tables others can register in, this too must happen before the handoffs.
+    let tables_input = dissector_tmp.get_dissector_tables();
+    if !tables_input.is_empty() {
+        let mut tables: Vec<(dissector::DissectorTableDefinition, epan::DissectorTable)> = Vec::new();
+        for definition in tables_input {
+            let (field_type, param) = match definition.key {
+                dissector::DissectorTableKey::UInt { field_type, display } => (field_type.into(), display as i32),
+                dissector::DissectorTableKey::String => {
+                    (epan::ftypes::ftenum::STRING, epan::packet::STRING_CASE_SENSITIVE)
+                }
+            };
+            let table = unsafe {
+                epan::DissectorTable::from_ptr(epan::packet::register_dissector_table(
+                    util::perm_string_ptr(definition.name),
+                    util::perm_string_ptr(definition.ui_name),
+                    proto_id,
+                    field_type,
+                    param,
+                ))
+            };
+            tables.push((definition, table));
+        }
+        dissector_tmp.set_dissector_tables(tables);
+    }
+
+    // Likewise for the heuristic lists.
+    let lists_input = dissector_tmp.get_heuristic_lists();
+    if !lists_input.is_empty() {
+        let mut lists: Vec<(&'static str, epan::HeuristicList)> = Vec::new();
+        for name in lists_input {
+            let list = unsafe {
+                epan::HeuristicList::from_ptr(epan::packet::register_heur_dissector_list(
+                    util::perm_string_ptr(name),
+                    proto_id,
+                ))
+            };
+            lists.push((name, list));
+        }
+        dissector_tmp.set_heuristic_lists(lists);
+    }
+
+    // Register the preferences, the protocol only gets a preferences module if it has any.
+    let preferences_input = dissector_tmp.get_preferences();
+    if !preferences_input.is_empty() {
+        let mut module = unsafe {
+            epan::PrefsModule::from_ptr(epan::prefs::prefs_register_protocol(
+                proto_id,
+                Some(TRAMPOLINES[INDEX].apply_preferences),
+            ))
+        };
+        let mut preferences: Vec<(dissector::PreferenceDefinition, epan::Preference)> = Vec::new();
+        for definition in preferences_input {
+            let (name, title, description) = (definition.name, definition.title, definition.description);
+            let preference = match &definition.kind {
+                dissector::PreferenceKind::Bool { default } => module.register_bool(name, title, description, *default),
+                dissector::PreferenceKind::UInt { default, base } => {
+                    module.register_uint(name, title, description, *base, *default)
+                }
+                dissector::PreferenceKind::Enum {
+                    default,
+                    values,
+                    radio_buttons,
+                } => module.register_enum(name, title, description, values, *radio_buttons, *default),
+                dissector::PreferenceKind::String { default } => {
+                    module.register_string(name, title, description, default)
+                }
+                dissector::PreferenceKind::Filename { default, for_writing } => {
+                    module.register_filename(name, title, description, default, *for_writing)
+                }
+                dissector::PreferenceKind::Range { default, max_value } => {
+                    module.register_range(name, title, description, default, *max_value)
+                }
+            };
+            preferences.push((definition, preference));
+        }
+        dissector_tmp.set_preferences(preferences);
+    }
+
+    // Create the handle here already, such that it is available to other dissectors during their handoff.
+    // If the dissector has names it's registered under those, otherwise it gets an anonymous handle.
+    let names = dissector_tmp.get_dissector_names();
+    let handles: Vec<epan::packet::dissector_handle_t> = names
+        .iter()
+        .map(|name| unsafe {
+            epan::packet::register_dissector(util::perm_string_ptr(name), Some(TRAMPOLINES[INDEX].dissect), proto_id)
+        })
+        .collect();
+    let handle = match handles.first() {
+        Some(handle) => *handle,
+        None => unsafe { epan::packet::create_dissector_handle(Some(TRAMPOLINES[INDEX].dissect), proto_id) },
+    };
+
+    // Registration is done, from now on the dissector is immutable.
+    let _ = REGISTERED[INDEX].set(RegisteredDissector {
+        dissector: dissector_tmp,
+        proto_id,
+        handle: unsafe { epan::DissectorHandle::from_ptr(handle) },
+    });
+}
+
+/// Called by wireshark when the preferences of the protocol in slot `INDEX` are applied.
+extern "C" fn preferences_apply<const INDEX: usize>() {
+    // Wireshark also applies the preferences while loading them, that may happen before registration is complete.
+    if let Some(entry) = registered(INDEX) {
+        entry.dissector.preferences_changed();
+    }
+}
+
+/// Build the range wireshark takes from the min-max pairs, only the first ones fit if there are more than it holds.
+fn uint_range(ranges: &[(u32, u32)]) -> epan::range::epan_range {
+    let mut range: epan::range::epan_range = Default::default();
+    let count = ranges.len().min(range.ranges.len());
+    for i in 0..count {
+        range.ranges[i].low = ranges[i].0;
+        range.ranges[i].high = ranges[i].1;
+    }
+    range.nranges = count as u32;
+    return range;
 }
 
-/// Global handoff function to register the dissector.
-extern "C" fn proto_register_handoff() {
+/// Handoff function to register the dissector in slot `INDEX`.
+extern "C" fn proto_register_handoff<const INDEX: usize>() {
     // A handoff routine associates a protocol handler with the protocol’s traffic. It consists of two major steps:
     // The first step is to create a dissector handle, which is a handle associated with the protocol and the function called to do the actual dissecting.
     // The second step is to register the dissector handle so that traffic associated with the protocol calls the dissector.
 
-    unsafe {
-        let dissector_tmp = &DISSECTOR_PTR.as_ref().unwrap();
-
-        let dissector_handle = epan::packet::create_dissector_handle(Some(dissect_protocol_function), PROTO_ID);
+    let entry = match registered(INDEX) {
+        Some(entry) => entry,
+        None => return,
+    };
+    let dissector_handle: epan::packet::dissector_handle_t = entry.handle.into();
 
-        for registration in dissector_tmp.get_registration() {
+    unsafe {
+        for registration in entry.dissector.get_registration() {
             match registration {
                 // Register as a post dissector
                 dissector::Registration::Post {} => {
@@ -180,14 +710,24 @@ extern "C" fn proto_register_handoff() {
                     epan::packet::dissector_add_uint(util::perm_string_ptr(abbrev), pattern, dissector_handle);
                 }
 
+                // Register in a specific table with a string.
+                dissector::Registration::String { abbrev, pattern } => {
+                    epan::packet::dissector_add_string(
+                        util::perm_string_ptr(abbrev),
+                        util::perm_string_ptr(pattern),
+                        dissector_handle,
+                    );
+                }
+
                 // Register in a specific table with ranges of integers.
                 dissector::Registration::UIntRange { abbrev, ranges } => {
-                    let mut input: epan::range::epan_range = Default::default();
-                    for i in 0..ranges.len() {
-                        input.ranges[i].low = ranges[i].0;
-                        input.ranges[i].high = ranges[i].1;
+                    let input = uint_range(&ranges);
+                    if ranges.len() > input.nranges as usize {
+                        report(&format!(
+                            "Only the first {} of the ranges for {} are registered.",
+                            input.nranges, abbrev
+                        ));
                     }
-                    input.nranges = input.ranges.len() as u32;
 
                     epan::packet::dissector_add_uint_range(
                         util::perm_string_ptr(abbrev),
@@ -196,6 +736,15 @@ extern "C" fn proto_register_handoff() {
                     );
                 }
 
+                // Register in a specific table with ranges of integers from a preference.
+                dissector::Registration::UIntRangePreference { abbrev, default } => {
+                    epan::packet::dissector_add_uint_range_with_preference(
+                        util::perm_string_ptr(abbrev),
+                        util::perm_string_ptr(default),
+                        dissector_handle,
+                    );
+                }
+
                 // Register for decode as functionality.
                 dissector::Registration::DecodeAs { abbrev } => {
                     epan::packet::dissector_add_for_decode_as(util::perm_string_ptr(abbrev), dissector_handle);
@@ -210,10 +759,10 @@ extern "C" fn proto_register_handoff() {
                 } => {
                     epan::packet::heur_dissector_add(
                         util::perm_string_ptr(table),
-                        Some(heuristic_dissector_function),
+                        Some(TRAMPOLINES[INDEX].heuristic),
                         util::perm_string_ptr(display_name),
                         util::perm_string_ptr(internal_name),
-                        PROTO_ID,
+                        entry.proto_id,
                         if enabled {
                             epan::packet::heuristic_enable_e::HEURISTIC_ENABLE
                         } else {
@@ -225,3 +774,30 @@ extern "C" fn proto_register_handoff() {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uint_range_holds_the_ranges() {
+        let range = uint_range(&[(1, 2), (10, 20)]);
+        assert_eq!(range.nranges, 2);
+        assert_eq!((range.ranges[0].low, range.ranges[0].high), (1, 2));
+        assert_eq!((range.ranges[1].low, range.ranges[1].high), (10, 20));
+    }
+
+    #[test]
+    fn uint_range_truncates_to_what_fits() {
+        let ranges: Vec<(u32, u32)> = (0..150).map(|i| (i, i + 1)).collect();
+        let range = uint_range(&ranges);
+        assert_eq!(range.nranges as usize, range.ranges.len());
+        let last = range.ranges.len() - 1;
+        assert_eq!((range.ranges[last].low, range.ranges[last].high), ranges[last]);
+    }
+
+    #[test]
+    fn uint_range_empty() {
+        assert_eq!(uint_range(&[]).nranges, 0);
+    }
+}
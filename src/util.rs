@@ -9,41 +9,37 @@ Such that if we require the same string in various places, we don't end up leaki
 use std::ffi::CStr;
 use std::ffi::CString;
 use std::os::raw::c_char;
+use std::sync::Mutex;
 
-static mut STRING_STORAGE: Option<Vec<*const i8>> = Some(Vec::new());
+// The strings are leaked on purpose, they must live for the remainder of the program.
+static STRING_STORAGE: Mutex<Vec<&'static CStr>> = Mutex::new(Vec::new());
 
 // Then we can make this function that returns
-pub fn perm_string(input: &str) -> &CStr {
-    let mut container_tmp: Option<Vec<*const i8>>;
+pub fn perm_string(input: &str) -> &'static CStr {
+    // C strings end at the first nul byte, so that's all wireshark would ever see of this string.
+    let input = input.split('\0').next().unwrap_or("");
 
-    // Move our string storage from the static global to our local container.
-    unsafe {
-        container_tmp = Some(STRING_STORAGE.take().unwrap());
-    }
-
-    let mut result: Option<*const i8> = None;
+    // A panic while holding the lock doesn't invalidate the vector, so ignore poisoning.
+    let mut storage = STRING_STORAGE.lock().unwrap_or_else(|e| e.into_inner());
 
-    for stored_string in container_tmp.as_mut().unwrap().iter() {
-        unsafe {
-            if CStr::from_ptr(*stored_string).to_str().unwrap() == input {
-                result = Some(*stored_string);
-                break;
-            }
-        }
-    }
-    if result.is_some() {
-        unsafe {
-            STRING_STORAGE = Some(container_tmp.unwrap());
-            return CStr::from_ptr(result.unwrap());
+    for stored_string in storage.iter() {
+        if stored_string.to_bytes() == input.as_bytes() {
+            return stored_string;
         }
     }
 
-    let to_add = CString::new(input).unwrap().into_raw();
-    container_tmp.as_mut().unwrap().push(to_add);
-    unsafe {
-        STRING_STORAGE = Some(container_tmp.unwrap());
-    }
-    return perm_string(input);
+    let to_add: &'static CStr = match CString::new(input) {
+        Ok(s) => Box::leak(s.into_boxed_c_str()),
+        Err(_) => Default::default(), // can't happen, we stripped everything after the nul byte.
+    };
+    storage.push(to_add);
+    return to_add;
+}
+
+/// A temporary C string of the input, cut off at the first nul byte like wireshark would see it.
+pub fn c_string(input: &str) -> CString {
+    let input = input.split('\0').next().unwrap_or("");
+    return CString::new(input).unwrap_or_default(); // can't fail, we stripped everything after the nul byte.
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,105 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+use std::os::raw::c_char;
+
+// Provides MAJOR, MINOR, MICRO, VERSION and RELEASE, determined by the build script.
+include!(concat!(env!("OUT_DIR"), "/version.rs"));
+
+/// The version of the wireshark the plugin is running in, None if it can't be determined.
+pub fn running() -> Option<(u32, u32, u32)> {
+    let version = unsafe { crate::epan::epan::epan_get_version() };
+    if version.is_null() {
+        return None;
+    }
+    let version = unsafe { std::ffi::CStr::from_ptr(version) }.to_string_lossy();
+    // Development builds may have a suffix like "3.7.0-123-gabcdef", only the leading numbers matter.
+    let mut parts = version.split(|c: char| !c.is_ascii_digit());
+    let mut part = || parts.next().and_then(|p| p.parse::<u32>().ok());
+    return Some((part()?, part()?, part()?));
+}
+
+/// Whether the bindings fit the wireshark we're running in, the structs only change between minor releases so the
+/// major and minor versions must match those this crate was built for.
+pub fn is_compatible(running: (u32, u32, u32)) -> bool {
+    return running.0 == MAJOR && running.1 == MINOR;
+}
+
+/// Value for `plugin_describe` that marks the plugin as one providing dissectors, `WS_PLUGIN_DESC_DISSECTOR`.
+pub const WS_PLUGIN_DESC_DISSECTOR: u32 = 1 << 0;
+
+/// Convert a nul terminated string into a C character array at compile time, used for the exported version symbols.
+pub const fn c_array<const N: usize>(input: &str) -> [c_char; N] {
+    let bytes = input.as_bytes();
+    let mut result = [0 as c_char; N];
+    let mut i = 0;
+    while i < N && i < bytes.len() {
+        result[i] = bytes[i] as c_char;
+        i += 1;
+    }
+    result
+}
+
+/// Creates the symbols wireshark looks for when loading a plugin, this must be invoked exactly once in the plugin.
+///
+/// It takes one or more expressions that create a [`dissector::Dissector`](crate::dissector::Dissector), these are
+/// passed to [`dissector::setup()`](crate::dissector::setup) when wireshark calls `plugin_register`. Besides that
+/// function it exports `plugin_version` (from the plugin's own crate version), `plugin_release` (used by 2.6),
+/// `plugin_want_major` and `plugin_want_minor` (used by 3.x and later) and `plugin_describe` (used by 4.x), all
+/// filled in with the wireshark version found when building this crate.
+///
+/// ```ignore
+/// wireshark_dissector_rs::wireshark_plugin!(MyDissector::new());
+/// ```
+#[macro_export]
+macro_rules! wireshark_plugin {
+    ($($dissector:expr),+ $(,)?) => {
+        /// This function is the main entry point for the plugin. It's the only symbol called automatically.
+        #[no_mangle]
+        pub extern "C" fn plugin_register() {
+            $($crate::dissector::setup($dissector);)+
+        }
+
+        /// Tells wireshark what kind of plugin this is.
+        #[no_mangle]
+        pub extern "C" fn plugin_describe() -> u32 {
+            $crate::version::WS_PLUGIN_DESC_DISSECTOR
+        }
+
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static plugin_version: [::std::os::raw::c_char; concat!(env!("CARGO_PKG_VERSION"), "\0").len()] =
+            $crate::version::c_array(concat!(env!("CARGO_PKG_VERSION"), "\0"));
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static plugin_release: [::std::os::raw::c_char; $crate::version::RELEASE.len()] =
+            $crate::version::c_array($crate::version::RELEASE);
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static plugin_want_major: i32 = $crate::version::MAJOR as i32;
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static plugin_want_minor: i32 = $crate::version::MINOR as i32;
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_compatible_with_any_micro_release() {
+        assert!(is_compatible((MAJOR, MINOR, 0)));
+        assert!(is_compatible((MAJOR, MINOR, MICRO)));
+        assert!(is_compatible((MAJOR, MINOR, MICRO + 10)));
+    }
+
+    #[test]
+    fn is_not_compatible_with_other_minor_or_major_releases() {
+        assert!(!is_compatible((MAJOR, MINOR + 1, MICRO)));
+        assert!(!is_compatible((MAJOR + 1, MINOR, MICRO)));
+        if MINOR > 0 {
+            assert!(!is_compatible((MAJOR, MINOR - 1, MICRO)));
+        }
+    }
+}
@@ -0,0 +1,153 @@
+/*
+ * Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+ * SPDX-License-Identifier: GPL-2.0-or-later
+ *
+ * Sizes, alignments and offsets of the structs we mirror in Rust, as the installed headers see them. Compiled by the
+ * build script with the layout-tests feature, tests/layout.rs compares them against the Rust definitions. The build
+ * script defines WS_GE_<major>_<minor> for the same releases as the ws_ge_<major>_<minor> cfg flags.
+ */
+
+#include <stddef.h>
+#include <string.h>
+#include "bindings.h"
+#include <epan/range.h>
+#include <epan/expert.h>
+#include <epan/packet_info.h>
+#include <epan/frame_data.h>
+#include <epan/reassemble.h>
+
+struct layout_entry {
+    const char *name;
+    size_t value;
+};
+
+#define SIZE(type) { #type, sizeof(type) }, { #type " align", _Alignof(type) }
+#define OFFSET(type, field) { #type "." #field, offsetof(type, field) }
+
+const struct layout_entry wireshark_layout[] = {
+    SIZE(header_field_info),
+    OFFSET(header_field_info, name),
+    OFFSET(header_field_info, abbrev),
+    OFFSET(header_field_info, type),
+    OFFSET(header_field_info, display),
+    OFFSET(header_field_info, strings),
+    OFFSET(header_field_info, bitmask),
+    OFFSET(header_field_info, blurb),
+    OFFSET(header_field_info, id),
+    OFFSET(header_field_info, parent),
+    OFFSET(header_field_info, ref_type),
+    OFFSET(header_field_info, same_name_prev_id),
+    OFFSET(header_field_info, same_name_next),
+
+    SIZE(hf_register_info),
+    OFFSET(hf_register_info, p_id),
+    OFFSET(hf_register_info, hfinfo),
+
+    SIZE(field_info),
+    OFFSET(field_info, hfinfo),
+    OFFSET(field_info, start),
+    OFFSET(field_info, length),
+    OFFSET(field_info, appendix_start),
+    OFFSET(field_info, appendix_length),
+    OFFSET(field_info, tree_type),
+    OFFSET(field_info, flags),
+    OFFSET(field_info, rep),
+    OFFSET(field_info, ds_tvb),
+    OFFSET(field_info, value),
+#ifdef WS_GE_4_0
+    OFFSET(field_info, total_layer_num),
+    OFFSET(field_info, proto_layer_num),
+#else
+    SIZE(fvalue_t),
+#endif
+
+    SIZE(GPtrArray),
+    OFFSET(GPtrArray, pdata),
+    OFFSET(GPtrArray, len),
+
+    SIZE(range_admin_t),
+    OFFSET(range_admin_t, low),
+    OFFSET(range_admin_t, high),
+
+    /* The ranges are a flexible array member, so the size is that of the part before it. */
+    SIZE(epan_range),
+    OFFSET(epan_range, nranges),
+    OFFSET(epan_range, ranges),
+
+    SIZE(expert_field),
+    OFFSET(expert_field, ei),
+    OFFSET(expert_field, hf),
+
+    SIZE(expert_field_info),
+    OFFSET(expert_field_info, name),
+    OFFSET(expert_field_info, group),
+    OFFSET(expert_field_info, severity),
+    OFFSET(expert_field_info, summary),
+    OFFSET(expert_field_info, id),
+    OFFSET(expert_field_info, protocol),
+    OFFSET(expert_field_info, orig_severity),
+    OFFSET(expert_field_info, hf_info),
+
+    SIZE(ei_register_info),
+    OFFSET(ei_register_info, ids),
+    OFFSET(ei_register_info, eiinfo),
+
+    /* Only the start of these is mirrored, so there's no size. */
+    OFFSET(packet_info, cinfo),
+    OFFSET(packet_info, num),
+    OFFSET(packet_info, abs_ts),
+    OFFSET(packet_info, rel_ts),
+    OFFSET(packet_info, fd),
+    OFFSET(packet_info, src),
+    OFFSET(packet_info, dst),
+    OFFSET(packet_info, ptype),
+    OFFSET(packet_info, srcport),
+    OFFSET(packet_info, destport),
+    OFFSET(packet_info, match_uint),
+#ifdef WS_GE_4_0
+    OFFSET(packet_info, use_conv_addr_port_endpoints),
+    OFFSET(packet_info, conv_addr_port_endpoints),
+    OFFSET(packet_info, conv_elements),
+#else
+    OFFSET(packet_info, use_endpoint),
+    OFFSET(packet_info, conv_endpoint),
+#endif
+    OFFSET(packet_info, desegment_offset),
+    OFFSET(packet_info, desegment_len),
+    OFFSET(packet_info, p2p_dir),
+
+    OFFSET(frame_data, num),
+    OFFSET(frame_data, pfd),
+
+    SIZE(address),
+    OFFSET(address, type),
+    OFFSET(address, len),
+    OFFSET(address, data),
+
+    SIZE(reassembly_table),
+    SIZE(fragment_items),
+    OFFSET(fragment_items, ett_fragment),
+    OFFSET(fragment_items, hf_fragments),
+    OFFSET(fragment_items, hf_reassembled_in),
+    OFFSET(fragment_items, hf_reassembled_data),
+    OFFSET(fragment_items, tag),
+
+    SIZE(nstime_t),
+    OFFSET(nstime_t, secs),
+    OFFSET(nstime_t, nsecs),
+
+    { NULL, 0 },
+};
+
+/*
+ * The frame_data flags are bitfields, offsetof can't find them. This sets only visited on a zeroed frame_data, the
+ * Rust side checks that the bit it reads is the only one set.
+ */
+const unsigned char *wireshark_frame_data_visited(size_t *size)
+{
+    static frame_data fd;
+    memset(&fd, 0, sizeof(fd));
+    fd.visited = 1;
+    *size = sizeof(fd);
+    return (const unsigned char *)&fd;
+}
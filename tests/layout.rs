@@ -0,0 +1,286 @@
+// Copyright 2021-2021, Ivor Wanders and the wireshark_dissector_rs contributors
+// SPDX-License-Identifier: GPL-2.0-or-later
+
+// Compares the layout of the structs we mirror with the one the installed headers have, the C side of this lives in
+// layout.c. Run with `cargo test --features layout-tests`. The bindgen structs are generated from these same headers,
+// so only the hand written ones are checked.
+#![cfg(all(feature = "layout-tests", not(feature = "bindgen")))]
+
+use std::collections::HashMap;
+use std::ffi::CStr;
+use std::mem::{align_of, offset_of, size_of};
+
+use wireshark_dissector_rs::epan::address::address;
+use wireshark_dissector_rs::epan::expert::{ei_register_info, expert_field, expert_field_info};
+use wireshark_dissector_rs::epan::frame_data::{frame_data, FRAME_DATA_VISITED_BIT};
+use wireshark_dissector_rs::epan::ftypes::fvalue_t;
+use wireshark_dissector_rs::epan::glib::GPtrArray;
+use wireshark_dissector_rs::epan::nstime::nstime_t;
+use wireshark_dissector_rs::epan::packet_info::packet_info;
+use wireshark_dissector_rs::epan::proto::{field_info, header_field_info, hf_register_info};
+use wireshark_dissector_rs::epan::range::{epan_range, range_admin_t};
+use wireshark_dissector_rs::epan::reassemble::{fragment_items, reassembly_table};
+
+#[repr(C)]
+struct LayoutEntry {
+    name: *const libc::c_char,
+    value: libc::size_t,
+}
+
+extern "C" {
+    // Terminated by an entry with a null name.
+    static wireshark_layout: [LayoutEntry; 0];
+
+    // A zeroed frame_data with only the visited flag set, size is set to the size of the struct.
+    fn wireshark_frame_data_visited(size: *mut libc::size_t) -> *const u8;
+}
+
+/// The values from the C shim, by name.
+fn c_layout() -> HashMap<String, usize> {
+    let mut layout = HashMap::new();
+    unsafe {
+        let mut entry = wireshark_layout.as_ptr();
+        while !(*entry).name.is_null() {
+            let name = CStr::from_ptr((*entry).name).to_string_lossy().into_owned();
+            layout.insert(name, (*entry).value);
+            entry = entry.add(1);
+        }
+    }
+    return layout;
+}
+
+/// Collects the differences, so a single run reports everything that drifted.
+struct Checker {
+    c: HashMap<String, usize>,
+    mismatches: Vec<String>,
+}
+
+impl Checker {
+    fn check(self: &mut Self, name: &str, rust: usize) {
+        match self.c.get(name) {
+            Some(&c) if c == rust => {}
+            Some(&c) => self
+                .mismatches
+                .push(format!("{}: {} in the headers, {} in Rust", name, c, rust)),
+            None => self.mismatches.push(format!("{}: not provided by layout.c", name)),
+        }
+    }
+
+    fn finish(self: Self) {
+        assert!(
+            self.mismatches.is_empty(),
+            "Struct layout differs from the wireshark headers:\n  {}",
+            self.mismatches.join("\n  ")
+        );
+    }
+}
+
+macro_rules! check_size {
+    ($checker:expr, $type:ident) => {
+        $checker.check(stringify!($type), size_of::<$type>());
+        $checker.check(concat!(stringify!($type), " align"), align_of::<$type>());
+    };
+}
+
+macro_rules! check_offset {
+    ($checker:expr, $type:ident, $field:ident) => {
+        check_offset!($checker, $type, $field, $field);
+    };
+    // For fields that are named differently on the Rust side.
+    ($checker:expr, $type:ident, $field:ident, $c_field:ident) => {
+        $checker.check(
+            concat!(stringify!($type), ".", stringify!($c_field)),
+            offset_of!($type, $field),
+        );
+    };
+}
+
+fn checker() -> Checker {
+    Checker {
+        c: c_layout(),
+        mismatches: vec![],
+    }
+}
+
+#[test]
+fn header_field_info_layout() {
+    let mut c = checker();
+    check_size!(c, header_field_info);
+    check_offset!(c, header_field_info, name);
+    check_offset!(c, header_field_info, abbrev);
+    check_offset!(c, header_field_info, type_, type);
+    check_offset!(c, header_field_info, display);
+    check_offset!(c, header_field_info, strings);
+    check_offset!(c, header_field_info, bitmask);
+    check_offset!(c, header_field_info, blurb);
+    check_offset!(c, header_field_info, id);
+    check_offset!(c, header_field_info, parent);
+    check_offset!(c, header_field_info, ref_type);
+    check_offset!(c, header_field_info, same_name_pref_id, same_name_prev_id);
+    check_offset!(c, header_field_info, same_name_next);
+    c.finish();
+}
+
+#[test]
+fn hf_register_info_layout() {
+    let mut c = checker();
+    check_size!(c, hf_register_info);
+    check_offset!(c, hf_register_info, p_id);
+    check_offset!(c, hf_register_info, hfinfo);
+    c.finish();
+}
+
+#[test]
+fn field_info_layout() {
+    let mut c = checker();
+    check_size!(c, field_info);
+    check_offset!(c, field_info, hfinfo);
+    check_offset!(c, field_info, start);
+    check_offset!(c, field_info, length);
+    check_offset!(c, field_info, appendix_start);
+    check_offset!(c, field_info, appendix_length);
+    check_offset!(c, field_info, tree_type);
+    check_offset!(c, field_info, flags);
+    check_offset!(c, field_info, rep);
+    check_offset!(c, field_info, ds_tvb);
+    check_offset!(c, field_info, value);
+    #[cfg(ws_ge_4_0)]
+    {
+        check_offset!(c, field_info, total_layer_num);
+        check_offset!(c, field_info, proto_layer_num);
+    }
+    #[cfg(not(ws_ge_4_0))]
+    {
+        check_size!(c, fvalue_t);
+    }
+    c.finish();
+}
+
+#[test]
+fn g_ptr_array_layout() {
+    let mut c = checker();
+    check_size!(c, GPtrArray);
+    check_offset!(c, GPtrArray, pdata);
+    check_offset!(c, GPtrArray, len);
+    c.finish();
+}
+
+#[test]
+fn epan_range_layout() {
+    let mut c = checker();
+    check_size!(c, range_admin_t);
+    check_offset!(c, range_admin_t, low);
+    check_offset!(c, range_admin_t, high);
+
+    // Our ranges are a fixed size array where C has a flexible array member, so only the part in front of it counts.
+    c.check("epan_range", offset_of!(epan_range, ranges));
+    c.check("epan_range align", align_of::<epan_range>());
+    check_offset!(c, epan_range, nranges);
+    check_offset!(c, epan_range, ranges);
+    c.finish();
+}
+
+#[test]
+fn expert_layout() {
+    let mut c = checker();
+    check_size!(c, expert_field);
+    check_offset!(c, expert_field, ei);
+    check_offset!(c, expert_field, hf);
+
+    check_size!(c, expert_field_info);
+    check_offset!(c, expert_field_info, name);
+    check_offset!(c, expert_field_info, group);
+    check_offset!(c, expert_field_info, severity);
+    check_offset!(c, expert_field_info, summary);
+    check_offset!(c, expert_field_info, id);
+    check_offset!(c, expert_field_info, protocol);
+    check_offset!(c, expert_field_info, orig_severity);
+    check_offset!(c, expert_field_info, hf_info);
+
+    check_size!(c, ei_register_info);
+    check_offset!(c, ei_register_info, ids);
+    check_offset!(c, ei_register_info, eiinfo);
+    c.finish();
+}
+
+#[test]
+fn packet_info_layout() {
+    let mut c = checker();
+    check_offset!(c, packet_info, cinfo);
+    check_offset!(c, packet_info, num);
+    check_offset!(c, packet_info, abs_ts);
+    check_offset!(c, packet_info, rel_ts);
+    check_offset!(c, packet_info, fd);
+    check_offset!(c, packet_info, src);
+    check_offset!(c, packet_info, dst);
+    check_offset!(c, packet_info, ptype);
+    check_offset!(c, packet_info, srcport);
+    check_offset!(c, packet_info, destport);
+    check_offset!(c, packet_info, match_uint);
+    #[cfg(ws_ge_4_0)]
+    {
+        check_offset!(c, packet_info, use_endpoint, use_conv_addr_port_endpoints);
+        check_offset!(c, packet_info, conv_endpoint, conv_addr_port_endpoints);
+        check_offset!(c, packet_info, conv_elements);
+    }
+    #[cfg(not(ws_ge_4_0))]
+    {
+        check_offset!(c, packet_info, use_endpoint);
+        check_offset!(c, packet_info, conv_endpoint);
+    }
+    check_offset!(c, packet_info, desegment_offset);
+    check_offset!(c, packet_info, desegment_len);
+    check_offset!(c, packet_info, p2p_dir);
+
+    check_offset!(c, frame_data, num);
+    check_offset!(c, frame_data, pfd);
+
+    check_size!(c, address);
+    check_offset!(c, address, type_, type);
+    check_offset!(c, address, len);
+    check_offset!(c, address, data);
+
+    check_size!(c, nstime_t);
+    check_offset!(c, nstime_t, secs);
+    check_offset!(c, nstime_t, nsecs);
+    c.finish();
+}
+
+#[test]
+fn frame_data_visited() {
+    let c = unsafe {
+        let mut size = 0;
+        let data = wireshark_frame_data_visited(&mut size);
+        std::slice::from_raw_parts(data, size)
+    };
+    let offset = offset_of!(frame_data, flags);
+    assert!(
+        offset + size_of::<u32>() <= c.len(),
+        "frame_data.flags is past the end of the struct"
+    );
+
+    let mut expected = vec![0u8; c.len()];
+    expected[offset..offset + size_of::<u32>()].copy_from_slice(&(1u32 << FRAME_DATA_VISITED_BIT).to_ne_bytes());
+    assert_eq!(
+        c,
+        &expected[..],
+        "the visited flag is not in bit {} of frame_data.flags",
+        FRAME_DATA_VISITED_BIT
+    );
+
+    let fd = unsafe { &*(c.as_ptr() as *const frame_data) };
+    assert!(fd.visited());
+}
+
+#[test]
+fn reassembly_layout() {
+    let mut c = checker();
+    check_size!(c, reassembly_table);
+    check_size!(c, fragment_items);
+    check_offset!(c, fragment_items, ett_fragment);
+    check_offset!(c, fragment_items, hf_fragments);
+    check_offset!(c, fragment_items, hf_reassembled_in);
+    check_offset!(c, fragment_items, hf_reassembled_data);
+    check_offset!(c, fragment_items, tag);
+    c.finish();
+}